@@ -0,0 +1,339 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Slot based block authoring for collators.
+//!
+//! Time is divided into Aura slots and every slot is assigned to one of the authorities
+//! registered in the runtime, round robin. A collator only proposes a block in a slot that is
+//! assigned to one of the keys in its keystore, so several collators of the same parachain take
+//! turns instead of competing for every relay chain block.
+//!
+//! The author signs the parent hash and the slot with its Aura key and puts the signature in a
+//! pre-runtime digest next to the slot. It can not be a post-runtime seal: `validate_block` of
+//! cumulus executes the header as built, so the relay chain would reject a sealed block. The
+//! [`AuraVerifier`] of the import queue checks that the slot follows the slot of the parent,
+//! matches the timestamp of the block and that the signature belongs to the slot owner.
+
+use codec::{Decode, Encode};
+use futures::future::{self, Either};
+use log::debug;
+use parachain_primitives::AUTHOR_ENGINE_ID;
+use parachain_runtime::opaque::Block;
+use sc_keystore::KeyStorePtr;
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::{HeaderBackend, Result as ClientResult};
+use sp_consensus::{
+	import_queue::{BasicQueue, CacheKeyId, Verifier},
+	BlockImport, BlockImportParams, BlockOrigin, Environment, Error as ConsensusError,
+	ForkChoiceStrategy, Proposal, Proposer, RecordProof,
+};
+use sp_consensus_aura::{
+	inherents::AuraInherentData,
+	sr25519::{AuthorityId as AuraId, AuthorityPair as AuraPair, AuthoritySignature},
+	AuraApi, AURA_ENGINE_ID,
+};
+use sp_core::Pair;
+use sp_inherents::{InherentData, InherentDataProviders};
+use sp_runtime::{
+	generic::{BlockId, DigestItem},
+	traits::{Block as BlockT, DigestFor, Header as HeaderT},
+	Justification,
+};
+use sp_timestamp::TimestampInherentData;
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+type Hash = <Block as BlockT>::Hash;
+
+/// Read the Aura slot from the pre-runtime digest of the given header.
+fn slot_of(header: &<Block as BlockT>::Header) -> Option<u64> {
+	header.digest().logs().iter().find_map(|log| match log.as_pre_runtime() {
+		Some((id, mut data)) if id == AURA_ENGINE_ID => u64::decode(&mut data).ok(),
+		_ => None,
+	})
+}
+
+/// Read the signature of the slot owner from the pre-runtime digest of the given header.
+fn signature_of(header: &<Block as BlockT>::Header) -> Option<AuthoritySignature> {
+	header.digest().logs().iter().find_map(|log| match log.as_pre_runtime() {
+		Some((id, mut data)) if id == AUTHOR_ENGINE_ID => {
+			AuthoritySignature::decode(&mut data).ok()
+		}
+		_ => None,
+	})
+}
+
+/// The payload the slot owner signs to claim a slot on top of the given parent.
+fn claim_payload(parent_hash: &Hash, slot: u64) -> Vec<u8> {
+	(parent_hash, slot).encode()
+}
+
+/// Find the authority a slot is assigned to.
+fn slot_author(authorities: &[AuraId], slot: u64) -> Option<&AuraId> {
+	if authorities.is_empty() {
+		return None;
+	}
+	authorities.get((slot % authorities.len() as u64) as usize)
+}
+
+/// Wraps a proposer factory so that it only proposes in slots assigned to a local Aura key.
+pub struct SlotProposerFactory<C, PF> {
+	client: Arc<C>,
+	keystore: KeyStorePtr,
+	inner: PF,
+}
+
+impl<C, PF> SlotProposerFactory<C, PF> {
+	/// Create a new instance of `Self`.
+	pub fn new(client: Arc<C>, keystore: KeyStorePtr, inner: PF) -> Self {
+		Self {
+			client,
+			keystore,
+			inner,
+		}
+	}
+}
+
+impl<C, PF> Environment<Block> for SlotProposerFactory<C, PF>
+where
+	C: ProvideRuntimeApi<Block> + Send + Sync + 'static,
+	C::Api: AuraApi<Block, AuraId>,
+	PF: Environment<Block>,
+{
+	type Proposer = SlotProposer<PF::Proposer>;
+	type CreateProposer =
+		Pin<Box<dyn Future<Output = Result<Self::Proposer, Self::Error>> + Send + 'static>>;
+	type Error = PF::Error;
+
+	fn init(&mut self, parent: &<Block as BlockT>::Header) -> Self::CreateProposer {
+		let at = BlockId::Hash(parent.hash());
+		let api = self.client.runtime_api();
+		let authorities = match api.authorities(&at) {
+			Ok(authorities) => authorities,
+			Err(e) => {
+				let e = ConsensusError::ChainLookup(format!("Could not read authorities: {:?}", e));
+				return Box::pin(future::err(e.into()));
+			}
+		};
+		let slot_duration = match api.slot_duration(&at) {
+			Ok(slot_duration) => slot_duration,
+			Err(e) => {
+				let e =
+					ConsensusError::ChainLookup(format!("Could not read slot duration: {:?}", e));
+				return Box::pin(future::err(e.into()));
+			}
+		};
+		let parent_slot = slot_of(parent);
+		let parent_hash = parent.hash();
+		let keystore = self.keystore.clone();
+
+		let create_inner = self.inner.init(parent);
+
+		Box::pin(async move {
+			let inner = create_inner.await?;
+
+			Ok(SlotProposer {
+				inner,
+				authorities,
+				keystore,
+				slot_duration,
+				parent_slot,
+				parent_hash,
+			})
+		})
+	}
+}
+
+/// A proposer that only proposes in slots assigned to one of its keys.
+pub struct SlotProposer<P> {
+	inner: P,
+	authorities: Vec<AuraId>,
+	keystore: KeyStorePtr,
+	slot_duration: u64,
+	parent_slot: Option<u64>,
+	parent_hash: Hash,
+}
+
+impl<P> SlotProposer<P> {
+	/// Find the slot of the block to author and the key it is assigned to, if we have it.
+	fn claim_slot(&self, inherent_data: &InherentData) -> Result<(u64, AuraPair), ConsensusError> {
+		let timestamp = inherent_data
+			.timestamp_inherent_data()
+			.map_err(|e| ConsensusError::ChainLookup(format!("No timestamp: {:?}", e)))?;
+		let slot = timestamp / self.slot_duration;
+
+		if self.parent_slot.map_or(false, |parent_slot| slot <= parent_slot) {
+			debug!(target: "aura", "Slot {} already has a block, skipping", slot);
+			return Err(ConsensusError::CannotPropose);
+		}
+
+		let author =
+			slot_author(&self.authorities, slot).ok_or(ConsensusError::CannotPropose)?;
+
+		match self.keystore.read().key_pair::<AuraPair>(author) {
+			Ok(pair) => Ok((slot, pair)),
+			Err(_) => {
+				debug!(target: "aura", "Slot {} belongs to {:?}, skipping", slot, author);
+				Err(ConsensusError::CannotPropose)
+			}
+		}
+	}
+}
+
+impl<P: Proposer<Block>> Proposer<Block> for SlotProposer<P> {
+	type Transaction = P::Transaction;
+	type Proposal = Either<
+		future::Ready<Result<Proposal<Block, Self::Transaction>, Self::Error>>,
+		P::Proposal,
+	>;
+	type Error = P::Error;
+
+	fn propose(
+		self,
+		inherent_data: InherentData,
+		mut inherent_digests: DigestFor<Block>,
+		max_duration: Duration,
+		record_proof: RecordProof,
+	) -> Self::Proposal {
+		let (slot, pair) = match self.claim_slot(&inherent_data) {
+			Ok(claim) => claim,
+			Err(e) => return Either::Left(future::err(e.into())),
+		};
+		let signature = pair.sign(&claim_payload(&self.parent_hash, slot));
+
+		inherent_digests.push(DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode()));
+		inherent_digests.push(DigestItem::PreRuntime(AUTHOR_ENGINE_ID, signature.encode()));
+
+		Either::Right(self.inner.propose(
+			inherent_data,
+			inherent_digests,
+			max_duration,
+			record_proof,
+		))
+	}
+}
+
+/// Verifies the slot claim of imported blocks before handing them to the block import.
+pub struct AuraVerifier<C> {
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+}
+
+impl<C> AuraVerifier<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: AuraApi<Block, AuraId, Error = sp_blockchain::Error>,
+{
+	/// Check that the slot of the header follows its parent and is signed by its owner.
+	fn check_claim(&self, header: &<Block as BlockT>::Header) -> Result<u64, String> {
+		let parent_hash = *header.parent_hash();
+		let slot = slot_of(header).ok_or_else(|| "Header has no Aura slot".to_string())?;
+
+		let parent = self
+			.client
+			.header(BlockId::Hash(parent_hash))
+			.map_err(|e| format!("Could not read parent header: {:?}", e))?
+			.ok_or_else(|| format!("Parent header {:?} not found", parent_hash))?;
+		if slot_of(&parent).map_or(false, |parent_slot| slot <= parent_slot) {
+			return Err(format!("Slot {} does not follow the slot of the parent", slot));
+		}
+
+		let authorities = self
+			.client
+			.runtime_api()
+			.authorities(&BlockId::Hash(parent_hash))
+			.map_err(|e| format!("Could not read authorities: {:?}", e))?;
+		let author = slot_author(&authorities, slot)
+			.ok_or_else(|| "There are no Aura authorities".to_string())?;
+
+		let signature =
+			signature_of(header).ok_or_else(|| "Header has no author signature".to_string())?;
+		if !AuraPair::verify(&signature, claim_payload(&parent_hash, slot), author) {
+			return Err(format!("Slot {} is not signed by its owner {:?}", slot, author));
+		}
+
+		Ok(slot)
+	}
+}
+
+impl<C> Verifier<Block> for AuraVerifier<C>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync,
+	C::Api: AuraApi<Block, AuraId, Error = sp_blockchain::Error>
+		+ BlockBuilderApi<Block, Error = sp_blockchain::Error>,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: <Block as BlockT>::Header,
+		justification: Option<Justification>,
+		mut body: Option<Vec<<Block as BlockT>::Extrinsic>>,
+	) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let slot = self.check_claim(&header)?;
+
+		if let Some(inner_body) = body.take() {
+			// The runtime checks the timestamp of the block against the slot it is given, which
+			// is the slot of the header rather than the current one when syncing old blocks.
+			let mut inherent_data = self
+				.inherent_data_providers
+				.create_inherent_data()
+				.map_err(|e| e.into_string())?;
+			inherent_data.aura_replace_inherent_data(slot);
+
+			let at = BlockId::Hash(*header.parent_hash());
+			let block = Block::new(header.clone(), inner_body);
+			let inherent_res = self
+				.client
+				.runtime_api()
+				.check_inherents(&at, block.clone(), inherent_data)
+				.map_err(|e| format!("{:?}", e))?;
+
+			if !inherent_res.ok() {
+				inherent_res.into_errors().try_for_each(|(i, e)| {
+					Err(self.inherent_data_providers.error_to_string(&i, &e))
+				})?;
+			}
+
+			let (_, inner_body) = block.deconstruct();
+			body = Some(inner_body);
+		}
+
+		let post_hash = Some(header.hash());
+		let mut block_import_params = BlockImportParams::new(origin, header);
+		block_import_params.body = body;
+		block_import_params.justification = justification;
+		block_import_params.fork_choice = Some(ForkChoiceStrategy::Custom(false));
+		block_import_params.post_hash = post_hash;
+
+		Ok((block_import_params, None))
+	}
+}
+
+/// Start an import queue that verifies the slot claim of every block.
+pub fn import_queue<C, I>(
+	client: Arc<C>,
+	block_import: I,
+	inherent_data_providers: InherentDataProviders,
+	spawner: &impl sp_core::traits::SpawnNamed,
+	registry: Option<&substrate_prometheus_endpoint::Registry>,
+) -> ClientResult<BasicQueue<Block, I::Transaction>>
+where
+	I: BlockImport<Block, Error = ConsensusError> + Send + Sync + 'static,
+	I::Transaction: Send,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AuraApi<Block, AuraId, Error = sp_blockchain::Error>
+		+ BlockBuilderApi<Block, Error = sp_blockchain::Error>,
+{
+	let verifier = AuraVerifier {
+		client,
+		inherent_data_providers,
+	};
+
+	Ok(BasicQueue::new(
+		verifier,
+		Box::new(block_import),
+		None,
+		None,
+		spawner,
+		registry,
+	))
+}
@@ -2,12 +2,16 @@
 
 use cumulus_primitives::ParaId;
 use parachain_runtime::{
-    AccountId, BalancesConfig, GenesisConfig, ParachainInfoConfig, Signature, SudoConfig,
-    SystemConfig, WASM_BINARY,
+    AccountId, AssetDealerConfig, AssetMetadataConfig, AssetRegistryConfig, AuraConfig,
+    BalancesConfig, CollatorRewardsConfig, CollatorSelectionConfig, CouncilConfig,
+    DemocracyConfig, GenesisConfig, HrmpChannelsConfig, InboundQueueConfig, MaximumBlockWeight,
+    ParachainInfoConfig, Perbill, RegistrationPolicy, SessionConfig, SessionKeys, Signature,
+    SudoConfig, SystemConfig, TechnicalCommitteeConfig, TreasuryConfig, DOLLARS, WASM_BINARY,
 };
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
 use serde::{Deserialize, Serialize};
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair, Public};
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
@@ -48,6 +52,14 @@ where
     AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
+/// Helper function to generate the account and Aura key of a collator from seed
+pub fn get_collator_keys_from_seed(seed: &str) -> (AccountId, AuraId) {
+    (
+        get_account_id_from_seed::<sr25519::Public>(seed),
+        get_from_seed::<AuraId>(seed),
+    )
+}
+
 pub fn get_chain_spec(id: ParaId) -> Result<ChainSpec, String> {
     Ok(ChainSpec::from_genesis(
         "Generic Parachain Network",
@@ -57,6 +69,10 @@ pub fn get_chain_spec(id: ParaId) -> Result<ChainSpec, String> {
             testnet_genesis(
                 WASM_BINARY,
                 get_account_id_from_seed::<sr25519::Public>("Alice"),
+                vec![
+                    get_collator_keys_from_seed("Alice"),
+                    get_collator_keys_from_seed("Bob"),
+                ],
                 vec![
                     get_account_id_from_seed::<sr25519::Public>("Alice"),
                     get_account_id_from_seed::<sr25519::Public>("Bob"),
@@ -79,6 +95,7 @@ pub fn get_chain_spec(id: ParaId) -> Result<ChainSpec, String> {
 fn testnet_genesis(
     wasm_binary: &[u8],
     root_key: AccountId,
+    initial_authorities: Vec<(AccountId, AuraId)>,
     endowed_accounts: Vec<AccountId>,
     id: ParaId,
 ) -> GenesisConfig {
@@ -94,7 +111,57 @@ fn testnet_genesis(
                 .map(|k| (k, 1 << 63))
                 .collect(),
         }),
+        pallet_collator_selection: Some(CollatorSelectionConfig {
+            invulnerables: initial_authorities
+                .iter()
+                .map(|(account, _)| account.clone())
+                .collect(),
+            desired_candidates: 4,
+            candidacy_bond: 16 * DOLLARS,
+        }),
+        pallet_session: Some(SessionConfig {
+            keys: initial_authorities
+                .into_iter()
+                .map(|(account, aura)| (account.clone(), account, SessionKeys { aura }))
+                .collect(),
+        }),
+        // Aura authorities are set by the session pallet from the collator selection.
+        pallet_aura: Some(AuraConfig {
+            authorities: vec![],
+        }),
+        pallet_collator_rewards: Some(CollatorRewardsConfig {
+            fee_author_share: Perbill::from_percent(50),
+            tip_author_share: Perbill::from_percent(100),
+        }),
         pallet_sudo: Some(SudoConfig { key: root_key }),
+        // Governance stays inert until members are set, see the runtime for retiring `Sudo`.
+        pallet_collective_Instance1: Some(CouncilConfig {
+            members: vec![],
+            phantom: Default::default(),
+        }),
+        pallet_collective_Instance2: Some(TechnicalCommitteeConfig {
+            members: vec![],
+            phantom: Default::default(),
+        }),
+        pallet_democracy: Some(DemocracyConfig::default()),
+        pallet_treasury: Some(TreasuryConfig::default()),
+        pallet_asset_metadata: Some(AssetMetadataConfig { assets: vec![] }),
+        pallet_asset_registry: Some(AssetRegistryConfig {
+            policy: RegistrationPolicy::AutoCreate,
+            foreign_assets: vec![],
+        }),
+        pallet_asset_dealer: Some(AssetDealerConfig {
+            transfer_modes: vec![],
+            auto_refund: false,
+        }),
+        pallet_inbound_queue: Some(InboundQueueConfig {
+            max_service_weight: MaximumBlockWeight::get() / 4,
+        }),
         parachain_info: Some(ParachainInfoConfig { parachain_id: id }),
+        // Unverified index of the HRMP pallet of the relay chain; check it against the relay
+        // chain the parachain runs on and correct it with `hrmpChannels.setRelayHrmpPalletIndex`.
+        pallet_hrmp_channels: Some(HrmpChannelsConfig {
+            relay_hrmp_pallet_index: 18,
+        }),
     }
 }
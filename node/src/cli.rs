@@ -18,6 +18,15 @@ pub enum Subcommand {
 	/// Export the genesis wasm of the parachain.
 	#[structopt(name = "export-genesis-wasm")]
 	ExportGenesisWasm(ExportGenesisWasmCommand),
+
+	/// Generate a collator key, or import one from a secret URI, into the keystore.
+	#[structopt(name = "generate-collator-key")]
+	GenerateCollatorKey(GenerateCollatorKeyCommand),
+
+	/// Benchmark runtime pallets. Use `--pallet overhead` to measure the base weights of blocks
+	/// and extrinsics.
+	#[structopt(name = "benchmark")]
+	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
 
 /// Command for exporting the genesis state of the parachain
@@ -48,6 +57,22 @@ pub struct ExportGenesisWasmCommand {
 	pub chain: Option<String>,
 }
 
+/// Command for storing the collator key in the keystore of the node.
+#[derive(Debug, StructOpt)]
+pub struct GenerateCollatorKeyCommand {
+	/// Secret URI of the key to store. A new random key is generated if unspecified.
+	#[structopt(long)]
+	pub suri: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: sc_cli::KeystoreParams,
+}
+
 #[derive(Debug, StructOpt)]
 pub struct RunCmd {
 	#[structopt(flatten)]
@@ -56,6 +81,12 @@ pub struct RunCmd {
 	/// Id of the parachain this collator collates for.
 	#[structopt(long)]
 	pub parachain_id: Option<u32>,
+
+	/// File containing the secret URI of the collator key.
+	///
+	/// If unspecified, the collator key is looked up in the keystore of the node.
+	#[structopt(long, parse(from_os_str))]
+	pub collator_key_file: Option<PathBuf>,
 }
 
 impl std::ops::Deref for RunCmd {
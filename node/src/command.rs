@@ -2,21 +2,23 @@
 
 use crate::{
     chain_spec,
-    cli::{Cli, RelayChainCli, Subcommand},
+    cli::{Cli, GenerateCollatorKeyCommand, RelayChainCli, Subcommand},
 };
 use codec::Encode;
 use cumulus_primitives::ParaId;
 use log::info;
 use parachain_runtime::Block;
 use polkadot_parachain::primitives::AccountIdConversion;
+use polkadot_primitives::v0::{CollatorId, CollatorPair};
 use sc_cli::{
     ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
     NetworkParams, Result, RuntimeVersion, SharedParams, SubstrateCli,
 };
-use sc_service::config::{BasePath, PrometheusConfig};
-use sp_core::hexdisplay::HexDisplay;
+use sc_keystore::{KeyStorePtr, Store as Keystore};
+use sc_service::config::{BasePath, KeystoreConfig, PrometheusConfig};
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, Pair};
 use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, Zero};
-use std::{io::Write, net::SocketAddr, sync::Arc};
+use std::{io::Write, net::SocketAddr, path::Path, sync::Arc};
 
 fn load_spec(
     id: &str,
@@ -146,6 +148,48 @@ fn extract_genesis_wasm(chain_spec: &Box<dyn sc_service::ChainSpec>) -> Result<V
         .ok_or_else(|| "Could not find wasm file in genesis state!".into())
 }
 
+fn open_keystore(config: &KeystoreConfig) -> std::result::Result<KeyStorePtr, String> {
+    match config {
+        KeystoreConfig::Path { path, password } => Keystore::open(path.clone(), password.clone())
+            .map_err(|e| format!("Failed to open keystore at {}: {}", path.display(), e)),
+        KeystoreConfig::InMemory => Ok(Keystore::new_in_memory()),
+    }
+}
+
+/// Load the collator key from the given key file or, if none is given, from the keystore.
+fn load_collator_key(
+    keystore: &KeystoreConfig,
+    key_file: Option<&Path>,
+) -> std::result::Result<CollatorPair, String> {
+    if let Some(path) = key_file {
+        let suri = std::fs::read_to_string(path).map_err(|e| {
+            format!("Failed to read collator key file {}: {}", path.display(), e)
+        })?;
+
+        return CollatorPair::from_string(suri.trim(), None)
+            .map_err(|e| format!("Invalid collator key in {}: {:?}", path.display(), e));
+    }
+
+    let keystore = open_keystore(keystore)?;
+    let keystore = keystore.read();
+    let public = keystore
+        .public_keys::<CollatorId>()
+        .map_err(|e| format!("Failed to read collator keys from keystore: {}", e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            format!(
+                "No collator key found in the keystore. Generate one with `{} \
+                generate-collator-key` or pass `--collator-key-file`.",
+                Cli::executable_name()
+            )
+        })?;
+
+    keystore
+        .key_pair::<CollatorPair>(&public)
+        .map_err(|e| format!("Failed to load collator key {}: {}", public.to_ss58check(), e))
+}
+
 /// Parse command line arguments into service configuration.
 pub fn run() -> Result<()> {
     let cli = Cli::from_args();
@@ -199,12 +243,46 @@ pub fn run() -> Result<()> {
 
             Ok(())
         }
+        Some(Subcommand::GenerateCollatorKey(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+
+            runner.sync_run(|config| {
+                let keystore = open_keystore(&config.keystore)?;
+                let pair = match &cmd.suri {
+                    Some(suri) => keystore.write().insert::<CollatorPair>(suri),
+                    None => keystore.write().generate::<CollatorPair>(),
+                }
+                .map_err(|e| format!("Failed to store collator key: {}", e))?;
+
+                println!("{}", pair.public().to_ss58check());
+
+                Ok(())
+            })
+        }
+        Some(Subcommand::Benchmark(cmd)) => {
+            if cfg!(feature = "runtime-benchmarks") {
+                let runner = cli.create_runner(cmd)?;
+
+                runner.sync_run(|config| {
+                    cmd.run::<Block, crate::service::RuntimeExecutor>(config)
+                })
+            } else {
+                Err("Benchmarking wasn't enabled when building the node. \
+                    You can enable it with `--features runtime-benchmarks`."
+                    .into())
+            }
+        }
         None => {
             let runner = cli.create_runner(&*cli.run)?;
 
             runner.run_node_until_exit(|config| {
-                // TODO
-                let key = Arc::new(sp_core::Pair::generate().0);
+                // Only a collating node needs a stable identity, full nodes can use a throwaway key.
+                let key = if cli.run.base.validator {
+                    load_collator_key(&config.keystore, cli.run.collator_key_file.as_deref())?
+                } else {
+                    CollatorPair::generate().0
+                };
+                let key = Arc::new(key);
 
                 let extension = chain_spec::Extensions::try_get(&config.chain_spec);
                 let relay_chain_id = extension.map(|e| e.relay_chain.clone());
@@ -239,6 +317,9 @@ pub fn run() -> Result<()> {
                     "Is collating: {}",
                     if cli.run.base.validator { "yes" } else { "no" }
                 );
+                if cli.run.base.validator {
+                    info!("Collator key: {}", key.public().to_ss58check());
+                }
 
                 crate::service::start_node(config, key, polkadot_config, id, cli.run.base.validator)
                     .map(|r| r.0)
@@ -247,6 +328,16 @@ pub fn run() -> Result<()> {
     }
 }
 
+impl CliConfiguration for GenerateCollatorKeyCommand {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn keystore_params(&self) -> Option<&KeystoreParams> {
+        Some(&self.keystore_params)
+    }
+}
+
 impl DefaultConfigurationValues for RelayChainCli {
     fn p2p_listen_port() -> u16 {
         30334
@@ -5,11 +5,13 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+mod aura;
 mod chain_spec;
 #[macro_use]
 mod service;
 mod cli;
 mod command;
+mod rpc;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
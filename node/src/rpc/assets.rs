@@ -0,0 +1,157 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! RPCs for the assets of `pallet_assets`.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use cumulus_primitives::ParaId;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use parachain_primitives::AssetsApi as AssetsRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::U256;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of a failed runtime API call.
+const RUNTIME_ERROR: i64 = 1;
+
+/// Assets RPC methods. Balances are returned as hex to fit any balance type.
+#[rpc(server)]
+pub trait AssetsApi<BlockHash, AccountId, AssetId> {
+	/// Balance of `who` in the asset.
+	#[rpc(name = "assets_balance")]
+	fn balance(&self, asset_id: AssetId, who: AccountId, at: Option<BlockHash>) -> Result<U256>;
+
+	/// Total issuance of the asset.
+	#[rpc(name = "assets_totalSupply")]
+	fn total_supply(&self, asset_id: AssetId, at: Option<BlockHash>) -> Result<U256>;
+
+	/// The assets `who` holds and its balance of each.
+	#[rpc(name = "assets_accountAssets")]
+	fn account_assets(&self, who: AccountId, at: Option<BlockHash>) -> Result<Vec<(AssetId, U256)>>;
+
+	/// The local asset the asset `remote_id` of the sibling parachain `para_id` is minted as.
+	#[rpc(name = "assets_localAssetId")]
+	fn local_asset_id(
+		&self,
+		para_id: ParaId,
+		remote_id: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<Option<AssetId>>;
+
+	/// The sibling parachain and remote id of a local asset that represents a foreign one.
+	#[rpc(name = "assets_foreignAsset")]
+	fn foreign_asset(
+		&self,
+		asset_id: AssetId,
+		at: Option<BlockHash>,
+	) -> Result<Option<(ParaId, AssetId)>>;
+}
+
+/// Implementation of the assets RPCs on top of the `AssetsApi` of the runtime.
+pub struct Assets<C, Block, Balance> {
+	client: Arc<C>,
+	_marker: PhantomData<(Block, Balance)>,
+}
+
+impl<C, Block, Balance> Assets<C, Block, Balance> {
+	/// Create new `Assets` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query the assets.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, AssetId, Balance> AssetsApi<<Block as BlockT>::Hash, AccountId, AssetId>
+	for Assets<C, Block, Balance>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: AssetsRuntimeApi<Block, AccountId, AssetId, Balance>,
+	AccountId: Codec,
+	AssetId: Codec,
+	Balance: Codec + Into<U256> + Send + Sync + 'static,
+{
+	fn balance(
+		&self,
+		asset_id: AssetId,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<U256> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.balance(&at, asset_id, who)
+			.map(Into::into)
+			.map_err(runtime_error)
+	}
+
+	fn total_supply(&self, asset_id: AssetId, at: Option<<Block as BlockT>::Hash>) -> Result<U256> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.total_supply(&at, asset_id)
+			.map(Into::into)
+			.map_err(runtime_error)
+	}
+
+	fn account_assets(
+		&self,
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(AssetId, U256)>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.account_assets(&at, who)
+			.map(|assets| {
+				assets
+					.into_iter()
+					.map(|(asset_id, balance)| (asset_id, balance.into()))
+					.collect()
+			})
+			.map_err(runtime_error)
+	}
+
+	fn local_asset_id(
+		&self,
+		para_id: ParaId,
+		remote_id: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<AssetId>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.local_asset_id(&at, para_id, remote_id)
+			.map_err(runtime_error)
+	}
+
+	fn foreign_asset(
+		&self,
+		asset_id: AssetId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<(ParaId, AssetId)>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.foreign_asset(&at, asset_id)
+			.map_err(runtime_error)
+	}
+}
@@ -0,0 +1,59 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! RPCs for the HRMP channels of the parachain.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use parachain_primitives::{HrmpChannel, HrmpChannelsApi as HrmpChannelsRuntimeApi};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Error code of a failed runtime API call.
+const RUNTIME_ERROR: i64 = 1;
+
+/// HRMP channel RPC methods.
+#[rpc(server)]
+pub trait HrmpChannelsApi<BlockHash> {
+	/// The channels requested, accepted or being closed by the parachain.
+	#[rpc(name = "hrmp_channels")]
+	fn channels(&self, at: Option<BlockHash>) -> Result<Vec<HrmpChannel>>;
+}
+
+/// Implementation of the HRMP channel RPCs on top of the `HrmpChannelsApi` of the runtime.
+pub struct HrmpChannels<C, Block> {
+	client: Arc<C>,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> HrmpChannels<C, Block> {
+	/// Create new `HrmpChannels` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, Block> HrmpChannelsApi<<Block as BlockT>::Hash> for HrmpChannels<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: HrmpChannelsRuntimeApi<Block>,
+{
+	fn channels(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<HrmpChannel>> {
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		self.client
+			.runtime_api()
+			.hrmp_channels(&at)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Unable to query the HRMP channels.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
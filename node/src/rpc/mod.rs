@@ -0,0 +1,68 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! RPC extensions of the collator, on top of the RPCs of the Substrate service.
+
+use std::sync::Arc;
+
+use parachain_runtime::{opaque::Block, AccountId, AssetId, Balance, Index};
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder;
+use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_transaction_pool::TransactionPool;
+
+pub use sc_rpc_api::DenyUnsafe;
+
+pub mod assets;
+pub mod hrmp;
+
+/// Dependencies of the full node RPC extensions.
+pub struct FullDeps<C, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Whether to deny unsafe calls.
+	pub deny_unsafe: DenyUnsafe,
+}
+
+/// Instantiate the RPC extensions of a full node.
+pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>
+		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+		+ parachain_primitives::AssetsApi<Block, AccountId, AssetId, Balance>
+		+ parachain_primitives::HrmpChannelsApi<Block>
+		+ BlockBuilder<Block>,
+	P: TransactionPool + 'static,
+{
+	use assets::{Assets, AssetsApi};
+	use hrmp::{HrmpChannels, HrmpChannelsApi};
+	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let FullDeps {
+		client,
+		pool,
+		deny_unsafe,
+	} = deps;
+
+	io.extend_with(SystemApi::to_delegate(FullSystem::new(
+		client.clone(),
+		pool,
+		deny_unsafe,
+	)));
+	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(
+		client.clone(),
+	)));
+	io.extend_with(AssetsApi::to_delegate(Assets::<_, _, Balance>::new(client.clone())));
+	io.extend_with(HrmpChannelsApi::to_delegate(HrmpChannels::new(client)));
+
+	io
+}
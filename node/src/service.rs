@@ -6,13 +6,14 @@ use cumulus_service::{
 	prepare_node_config, start_collator, start_full_node, StartCollatorParams, StartFullNodeParams,
 };
 use polkadot_primitives::v0::CollatorPair;
-use parachain_runtime::opaque::Block;
+use parachain_runtime::{opaque::Block, AccountId, AssetId, Balance, Index};
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sc_informant::OutputFormat;
 use sc_service::{Configuration, PartialComponents, Role, TFullBackend, TFullClient, TaskManager};
-use sp_api::ConstructRuntimeApi;
-use sp_runtime::traits::BlakeTwo256;
+use sp_api::{ConstructRuntimeApi, ProvideRuntimeApi};
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_runtime::{generic::BlockId, traits::BlakeTwo256};
 use sp_trie::PrefixedMemoryDB;
 use std::sync::Arc;
 
@@ -21,6 +22,7 @@ native_executor_instance!(
 	pub RuntimeExecutor,
 	parachain_runtime::api::dispatch,
 	parachain_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
 );
 
 /// Starts a `ServiceBuilder` for a full service.
@@ -53,7 +55,8 @@ where
 			Error = sp_blockchain::Error,
 			StateBackend = sc_client_api::StateBackendFor<TFullBackend<Block>, Block>,
 		> + sp_offchain::OffchainWorkerApi<Block>
-		+ sp_block_builder::BlockBuilder<Block>,
+		+ sp_block_builder::BlockBuilder<Block>
+		+ sp_consensus_aura::AuraApi<Block, AuraId>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
 	Executor: sc_executor::NativeExecutionDispatch + 'static,
 {
@@ -72,7 +75,18 @@ where
 		client.clone(),
 	);
 
-	let import_queue = cumulus_consensus::import_queue::import_queue(
+	let slot_duration = client
+		.runtime_api()
+		.slot_duration(&BlockId::number(0))
+		.map_err(|e| sc_service::Error::Other(format!("Could not read slot duration: {:?}", e)))?;
+	inherent_data_providers
+		.register_provider(sp_timestamp::InherentDataProvider)
+		.map_err(|e| sc_service::Error::Other(format!("{:?}", e)))?;
+	inherent_data_providers
+		.register_provider(sp_consensus_aura::inherents::InherentDataProvider::new(slot_duration))
+		.map_err(|e| sc_service::Error::Other(format!("{:?}", e)))?;
+
+	let import_queue = crate::aura::import_queue(
 		client.clone(),
 		client.clone(),
 		inherent_data_providers.clone(),
@@ -118,7 +132,12 @@ where
 			Error = sp_blockchain::Error,
 			StateBackend = sc_client_api::StateBackendFor<TFullBackend<Block>, Block>,
 		> + sp_offchain::OffchainWorkerApi<Block>
-		+ sp_block_builder::BlockBuilder<Block>,
+		+ sp_block_builder::BlockBuilder<Block>
+		+ sp_consensus_aura::AuraApi<Block, AuraId>
+		+ substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>
+		+ pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+		+ parachain_primitives::AssetsApi<Block, AccountId, AssetId, Balance>
+		+ parachain_primitives::HrmpChannelsApi<Block>,
 	sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
 	Executor: sc_executor::NativeExecutionDispatch + 'static,
 {
@@ -138,10 +157,6 @@ where
 	};
 
 	let params = new_partial::<RuntimeApi, Executor>(&mut parachain_config)?;
-	params
-		.inherent_data_providers
-		.register_provider(sp_timestamp::InherentDataProvider)
-		.unwrap();
 
 	let client = params.client.clone();
 	let backend = params.backend.clone();
@@ -155,6 +170,7 @@ where
 	let transaction_pool = params.transaction_pool.clone();
 	let mut task_manager = params.task_manager;
 	let import_queue = params.import_queue;
+	let keystore = params.keystore;
 	let (network, network_status_sinks, system_rpc_tx, start_network) =
 		sc_service::build_network(sc_service::BuildNetworkParams {
 			config: &parachain_config,
@@ -168,16 +184,31 @@ where
 			finality_proof_provider: None,
 		})?;
 
+	let rpc_extensions_builder = {
+		let client = client.clone();
+		let pool = transaction_pool.clone();
+
+		Box::new(move |deny_unsafe| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+			};
+
+			crate::rpc::create_full(deps)
+		})
+	};
+
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		on_demand: None,
 		remote_blockchain: None,
-		rpc_extensions_builder: Box::new(|_| ()),
+		rpc_extensions_builder,
 		client: client.clone(),
 		transaction_pool: transaction_pool.clone(),
 		task_manager: &mut task_manager,
 		telemetry_connection_sinks: Default::default(),
 		config: parachain_config,
-		keystore: params.keystore,
+		keystore: keystore.clone(),
 		backend,
 		network: network.clone(),
 		network_status_sinks,
@@ -187,10 +218,14 @@ where
 	let announce_block = Arc::new(move |hash, data| network.announce_block(hash, data));
 
 	if validator {
-		let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+		let proposer_factory = crate::aura::SlotProposerFactory::new(
 			client.clone(),
-			transaction_pool,
-			prometheus_registry.as_ref(),
+			keystore,
+			sc_basic_authorship::ProposerFactory::new(
+				client.clone(),
+				transaction_pool,
+				prometheus_registry.as_ref(),
+			),
 		);
 
 		let params = StartCollatorParams {
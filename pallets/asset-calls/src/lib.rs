@@ -0,0 +1,110 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that dispatches the calls of `pallet_assets` and reports the changes they make.
+//!
+//! `pallet_assets` tells nobody which balances its calls change. The runtime leaves its calls
+//! out of `construct_runtime!` and exposes the ones of this pallet instead, which call into
+//! `pallet_assets` and notify `OnAssetChange` of every balance they change. The weight of the
+//! notifications is part of the weight of the call.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{decl_module, traits::Get, weights::Weight};
+use frame_system::{ensure_signed, RawOrigin};
+use sp_runtime::traits::StaticLookup;
+
+#[cfg(test)]
+mod tests;
+
+/// Notified of the changes to the balances of the assets of `pallet_assets`.
+pub trait OnAssetChange<AccountId, AssetId> {
+	/// `who` issued a new asset.
+	fn on_issued(asset_id: AssetId, who: &AccountId);
+
+	/// The balance of `who` in the asset went up.
+	fn on_credited(asset_id: AssetId, who: &AccountId);
+
+	/// The balance of `who` in the asset went down.
+	fn on_debited(asset_id: AssetId, who: &AccountId);
+
+	/// The most one notification may weigh.
+	fn weight() -> Weight;
+}
+
+impl<AccountId, AssetId> OnAssetChange<AccountId, AssetId> for () {
+	fn on_issued(_asset_id: AssetId, _who: &AccountId) {}
+
+	fn on_credited(_asset_id: AssetId, _who: &AccountId) {}
+
+	fn on_debited(_asset_id: AssetId, _who: &AccountId) {}
+
+	fn weight() -> Weight {
+		0
+	}
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: pallet_assets::Trait {
+	/// Notified of the balances changed by the calls of this pallet.
+	type OnAssetChange: OnAssetChange<Self::AccountId, Self::AssetId>;
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// Issue a new asset with a total supply of `total`, held by the sender.
+		///
+		/// See `pallet_assets::issue`.
+		#[weight = 50_000_000
+			+ T::DbWeight::get().reads_writes(1, 3)
+			+ T::OnAssetChange::weight().saturating_mul(2)]
+		pub fn issue(origin, #[compact] total: T::Balance) {
+			let who = ensure_signed(origin)?;
+			let asset_id = <pallet_assets::Module<T>>::next_asset_id();
+
+			<pallet_assets::Module<T>>::issue(RawOrigin::Signed(who.clone()).into(), total)?;
+
+			T::OnAssetChange::on_issued(asset_id, &who);
+			T::OnAssetChange::on_credited(asset_id, &who);
+		}
+
+		/// Move `amount` of an asset from the sender to `target`.
+		///
+		/// See `pallet_assets::transfer`.
+		#[weight = 50_000_000
+			+ T::DbWeight::get().reads_writes(2, 2)
+			+ T::OnAssetChange::weight().saturating_mul(2)]
+		pub fn transfer(
+			origin,
+			#[compact] asset_id: T::AssetId,
+			target: <T::Lookup as StaticLookup>::Source,
+			#[compact] amount: T::Balance,
+		) {
+			let who = ensure_signed(origin)?;
+			let target = T::Lookup::lookup(target)?;
+
+			<pallet_assets::Module<T>>::transfer(
+				RawOrigin::Signed(who.clone()).into(),
+				asset_id,
+				T::Lookup::unlookup(target.clone()),
+				amount,
+			)?;
+
+			T::OnAssetChange::on_debited(asset_id, &who);
+			T::OnAssetChange::on_credited(asset_id, &target);
+		}
+
+		/// Destroy the whole balance of an asset held by the sender.
+		///
+		/// See `pallet_assets::destroy`.
+		#[weight = 30_000_000
+			+ T::DbWeight::get().reads_writes(2, 2)
+			+ T::OnAssetChange::weight()]
+		pub fn destroy(origin, #[compact] asset_id: T::AssetId) {
+			let who = ensure_signed(origin)?;
+
+			<pallet_assets::Module<T>>::destroy(RawOrigin::Signed(who.clone()).into(), asset_id)?;
+
+			T::OnAssetChange::on_debited(asset_id, &who);
+		}
+	}
+}
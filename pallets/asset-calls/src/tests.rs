@@ -0,0 +1,190 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the notifications of the asset calls.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_origin, parameter_types, weights::GetDispatchInfo,
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+use std::cell::RefCell;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+impl pallet_assets::Trait for Test {
+	type Event = ();
+	type Balance = u64;
+	type AssetId = u32;
+}
+
+/// A change reported to `OnAssetChange`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+	Issued(u32, u64),
+	Credited(u32, u64),
+	Debited(u32, u64),
+}
+
+thread_local! {
+	static CHANGES: RefCell<Vec<Change>> = RefCell::new(Vec::new());
+}
+
+/// Records the changes it is notified of.
+pub struct RecordChanges;
+
+impl OnAssetChange<u64, u32> for RecordChanges {
+	fn on_issued(asset_id: u32, who: &u64) {
+		CHANGES.with(|c| c.borrow_mut().push(Change::Issued(asset_id, *who)));
+	}
+
+	fn on_credited(asset_id: u32, who: &u64) {
+		CHANGES.with(|c| c.borrow_mut().push(Change::Credited(asset_id, *who)));
+	}
+
+	fn on_debited(asset_id: u32, who: &u64) {
+		CHANGES.with(|c| c.borrow_mut().push(Change::Debited(asset_id, *who)));
+	}
+
+	fn weight() -> Weight {
+		10
+	}
+}
+
+impl Trait for Test {
+	type OnAssetChange = RecordChanges;
+}
+
+type Assets = pallet_assets::Module<Test>;
+type AssetCalls = Module<Test>;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
+
+fn changes() -> Vec<Change> {
+	CHANGES.with(|c| c.borrow_mut().drain(..).collect())
+}
+
+#[test]
+fn issue_reports_the_issuer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetCalls::issue(Origin::signed(ALICE), 100));
+		assert_ok!(AssetCalls::issue(Origin::signed(BOB), 50));
+
+		assert_eq!(Assets::balance(0, ALICE), 100);
+		assert_eq!(Assets::balance(1, BOB), 50);
+		assert_eq!(
+			changes(),
+			vec![
+				Change::Issued(0, ALICE),
+				Change::Credited(0, ALICE),
+				Change::Issued(1, BOB),
+				Change::Credited(1, BOB),
+			],
+		);
+	});
+}
+
+#[test]
+fn transfer_reports_both_accounts() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetCalls::issue(Origin::signed(ALICE), 100));
+		changes();
+
+		assert_ok!(AssetCalls::transfer(Origin::signed(ALICE), 0, BOB, 40));
+
+		assert_eq!(Assets::balance(0, ALICE), 60);
+		assert_eq!(Assets::balance(0, BOB), 40);
+		assert_eq!(changes(), vec![Change::Debited(0, ALICE), Change::Credited(0, BOB)]);
+	});
+}
+
+#[test]
+fn destroy_reports_the_holder() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetCalls::issue(Origin::signed(ALICE), 100));
+		changes();
+
+		assert_ok!(AssetCalls::destroy(Origin::signed(ALICE), 0));
+
+		assert_eq!(Assets::balance(0, ALICE), 0);
+		assert_eq!(changes(), vec![Change::Debited(0, ALICE)]);
+	});
+}
+
+#[test]
+fn failed_calls_report_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetCalls::issue(Origin::signed(ALICE), 100));
+		changes();
+
+		assert_noop!(
+			AssetCalls::transfer(Origin::signed(BOB), 0, ALICE, 1),
+			pallet_assets::Error::<Test>::BalanceLow,
+		);
+		assert_noop!(
+			AssetCalls::destroy(Origin::signed(BOB), 0),
+			pallet_assets::Error::<Test>::BalanceZero,
+		);
+		assert!(changes().is_empty());
+	});
+}
+
+#[test]
+fn calls_are_weighed_with_their_notifications() {
+	let issue = Call::<Test>::issue(100).get_dispatch_info().weight;
+	let transfer = Call::<Test>::transfer(0, BOB, 40).get_dispatch_info().weight;
+	let destroy = Call::<Test>::destroy(0).get_dispatch_info().weight;
+
+	assert_eq!(issue, 50_000_000 + 2 * 10);
+	assert_eq!(transfer, 50_000_000 + 2 * 10);
+	assert_eq!(destroy, 30_000_000 + 10);
+}
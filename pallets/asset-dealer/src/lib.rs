@@ -0,0 +1,580 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that transfers `pallet_assets` assets to and from sibling parachains.
+//!
+//! The messages are the XCMP messages of `TokenDealer`. How a local asset leaves the parachain is
+//! set per asset by `UpdateOrigin`:
+//! - `BurnMint`: the amount is burned when sent and minted when it comes back;
+//! - `Reserve`: the amount is moved into the sovereign account of the destination and released
+//!   from it when it comes back.
+//!
+//! Local assets without a transfer mode cannot be sent. Assets of sibling parachains, mapped to
+//! local assets by the asset registry, are burned when sent back to their parachain.
+//!
+//! Every local asset has a ledger of the amount sent to each parachain and not returned, and
+//! more than that amount is never accepted back. Every foreign asset has a ledger of the amount
+//! held for this parachain on its home parachain. Root can reconcile both against the total
+//! supply and the sovereign accounts with `reconcile`.
+//!
+//! Received transfers that cannot be deposited, e.g. of unknown assets or native amounts below
+//! the existential deposit, are not lost. If `AutoRefund` is set they are sent back to the
+//! beneficiary on the sending parachain, otherwise they are kept as failed deposits the
+//! beneficiary can `claim` into an account of their choice or `refund`. While `OutboundPaused`
+//! is set nothing is sent back, failed deposits are kept whatever `AutoRefund` says.
+//!
+//! Every received transfer is first offered to `DepositFilter`, which may hold it back.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives::{xcmp::XCMPMessageSender, ParaId};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchResult,
+	ensure,
+	traits::{Currency, EnsureOrigin, Get},
+	transactional, IterableStorageDoubleMap,
+};
+use frame_system::{ensure_root, ensure_signed};
+use pallet_generic_token_dealer::XCMPMessage;
+use parachain_primitives::{MultiAsset, ReserveReconciliation};
+use sp_runtime::{
+	traits::{AccountIdConversion, Saturating, Zero},
+	RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(test)]
+mod tests;
+
+type AssetsOf<T> = <T as pallet_asset_registry::Trait>::Assets;
+type AssetIdOf<T> = <AssetsOf<T> as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<AssetsOf<T> as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+type AssetRegistry<T> = pallet_asset_registry::Module<T>;
+type FailedDepositOf<T> = FailedDeposit<AssetIdOf<T>, AssetBalanceOf<T>>;
+
+/// How a local asset is moved to other parachains.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum TransferMode {
+	/// Burned when sent, minted when it comes back.
+	BurnMint,
+	/// Held in the sovereign account of the destination, released when it comes back.
+	Reserve,
+}
+
+/// Identifier of a failed deposit.
+pub type DepositId = u64;
+
+/// A received transfer that could not be deposited.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct FailedDeposit<AssetId, Balance> {
+	/// The parachain that sent it.
+	pub origin: ParaId,
+	/// The asset as identified in the message, `None` for the native token.
+	pub asset_id: Option<AssetId>,
+	/// The amount sent.
+	pub amount: Balance,
+}
+
+/// Decides whether received transfers may be deposited.
+pub trait DepositFilter<AccountId, AssetId, Balance> {
+	/// Whether `amount` of the asset, as identified by `para_id`, may be deposited into `dest`.
+	/// `None` is the native token. A transfer that may not is taken over by the filter.
+	fn admit(para_id: ParaId, asset_id: Option<AssetId>, dest: &AccountId, amount: Balance) -> bool;
+}
+
+/// Admits every transfer.
+impl<AccountId, AssetId, Balance> DepositFilter<AccountId, AssetId, Balance> for () {
+	fn admit(_: ParaId, _: Option<AssetId>, _: &AccountId, _: Balance) -> bool {
+		true
+	}
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: pallet_asset_registry::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// Sends the XCMP messages.
+	type XCMPMessageSender: XCMPMessageSender<
+		XCMPMessage<Self::AccountId, AssetBalanceOf<Self>, AssetIdOf<Self>>,
+	>;
+
+	/// The native token received through `TokenDealer`.
+	type Currency: Currency<Self::AccountId, Balance = AssetBalanceOf<Self>>;
+
+	/// Origin that may set transfer modes and whether failed deposits are refunded.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Decides whether received transfers may be deposited.
+	type DepositFilter: DepositFilter<Self::AccountId, AssetIdOf<Self>, AssetBalanceOf<Self>>;
+
+	/// Whether transfers to other chains are paused.
+	type OutboundPaused: Get<bool>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetDealer {
+		/// How every transferable local asset is moved to other parachains.
+		TransferModes get(fn transfer_mode):
+			map hasher(twox_64_concat) AssetIdOf<T> => Option<TransferMode>;
+
+		/// Amount of every local asset sent to a sibling parachain and not returned. In
+		/// `Reserve` mode it is held in the sovereign account of the parachain.
+		Reserves get(fn reserve): double_map hasher(twox_64_concat) AssetIdOf<T>,
+			hasher(twox_64_concat) ParaId => AssetBalanceOf<T>;
+
+		/// Amount of every foreign asset held for this parachain on its home parachain: the
+		/// amount received minus the amount sent back.
+		Backing get(fn backing): map hasher(twox_64_concat) AssetIdOf<T> => AssetBalanceOf<T>;
+
+		/// The received transfers that could not be deposited, by beneficiary.
+		FailedDeposits get(fn failed_deposit): double_map hasher(blake2_128_concat) T::AccountId,
+			hasher(twox_64_concat) DepositId => Option<FailedDepositOf<T>>;
+
+		/// The identifier of the next failed deposit.
+		NextDepositId: DepositId;
+
+		/// Whether received transfers that cannot be deposited are sent back right away.
+		AutoRefund get(fn auto_refund) config(): bool;
+	}
+	add_extra_genesis {
+		/// Local assets and their transfer modes.
+		config(transfer_modes): Vec<(AssetIdOf<T>, TransferMode)>;
+		build(|config| {
+			for (asset_id, mode) in &config.transfer_modes {
+				<TransferModes<T>>::insert(asset_id, mode);
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+		AssetBalance = AssetBalanceOf<T>,
+	{
+		/// An asset was sent from the account to the account on the parachain.
+		Sent(AssetId, AssetBalance, AccountId, ParaId, AccountId),
+		/// A local asset came back from the parachain into the account.
+		Returned(ParaId, AssetId, AccountId, AssetBalance),
+		/// A local asset came back from the parachain, but could not be released to the account.
+		ReturnFailed(ParaId, AssetId, AccountId, AssetBalance),
+		/// The transfer mode of a local asset was set, or removed if `None`.
+		TransferModeSet(AssetId, Option<TransferMode>),
+		/// The amount of a local asset on the parachain changed to the given amount.
+		ReserveUpdated(AssetId, ParaId, AssetBalance),
+		/// The amount of a foreign asset held on its home parachain changed to the given amount.
+		BackingUpdated(AssetId, AssetBalance),
+		/// The reserves of an asset were reconciled: its total supply and the shortfall.
+		Reconciled(AssetId, AssetBalance, AssetBalance),
+		/// A transfer from the parachain to the account could not be deposited and was kept.
+		DepositFailed(DepositId, ParaId, Option<AssetId>, AccountId, AssetBalance),
+		/// A failed deposit was claimed into the account.
+		DepositClaimed(DepositId, AccountId),
+		/// A transfer that could not be deposited was sent back to the account on the parachain.
+		DepositRefunded(ParaId, Option<AssetId>, AccountId, AssetBalance),
+		/// Whether failed deposits are sent back right away was set.
+		AutoRefundSet(bool),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The asset has no transfer mode.
+		NotTransferable,
+		/// Assets of a sibling parachain can only be sent back to it.
+		NotHomeParachain,
+		/// The XCMP message could not be sent.
+		SendFailed,
+		/// More of a local asset came back from a parachain than was sent to it.
+		InsufficientReserve,
+		/// The native amount would leave the account below the existential deposit.
+		BelowMinimumBalance,
+		/// The sender has no failed deposit with the given identifier.
+		UnknownDeposit,
+		/// Transfers to other chains are paused.
+		OutboundPaused,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Send `amount` of the asset to the account `dest` on the parachain `para_id`.
+		#[weight = 100_000_000 + T::DbWeight::get().reads_writes(6, 5)]
+		pub fn transfer_to_parachain(
+			origin,
+			para_id: ParaId,
+			dest: T::AccountId,
+			asset_id: AssetIdOf<T>,
+			#[compact] amount: AssetBalanceOf<T>,
+		) {
+			let who = ensure_signed(origin)?;
+			ensure!(!T::OutboundPaused::get(), Error::<T>::OutboundPaused);
+
+			let foreign_asset = AssetRegistry::<T>::foreign_asset(asset_id);
+			let remote_id = match foreign_asset {
+				Some((home, remote_id)) => {
+					ensure!(home == para_id, Error::<T>::NotHomeParachain);
+					AssetsOf::<T>::withdraw(asset_id, &who, amount)?;
+					remote_id
+				}
+				None => {
+					let mode = Self::transfer_mode(asset_id).ok_or(Error::<T>::NotTransferable)?;
+					Self::take(mode, para_id, asset_id, &who, amount)?;
+					asset_id
+				}
+			};
+
+			let msg = XCMPMessage::TransferToken(dest.clone(), amount, Some(remote_id));
+			if T::XCMPMessageSender::send_xcmp_message(para_id, &msg).is_err() {
+				Self::give_back(para_id, asset_id, &who, amount)?;
+				return Err(Error::<T>::SendFailed.into());
+			}
+
+			match foreign_asset {
+				Some(_) => Self::mutate_backing(asset_id, |backing| backing.saturating_sub(amount)),
+				None => Self::mutate_reserve(asset_id, para_id, |reserve| {
+					reserve.saturating_add(amount)
+				}),
+			}
+
+			Self::deposit_event(RawEvent::Sent(asset_id, amount, who, para_id, dest));
+		}
+
+		/// Set how a local asset is moved to other parachains, or make it non-transferable if
+		/// `None`.
+		///
+		/// Amounts already sent in another mode cannot come back after the mode is changed.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_transfer_mode(origin, asset_id: AssetIdOf<T>, mode: Option<TransferMode>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			match mode {
+				Some(mode) => <TransferModes<T>>::insert(asset_id, mode),
+				None => <TransferModes<T>>::remove(asset_id),
+			}
+			Self::deposit_event(RawEvent::TransferModeSet(asset_id, mode));
+		}
+
+		/// Set whether received transfers that cannot be deposited are sent back right away.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_auto_refund(origin, auto_refund: bool) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			AutoRefund::put(auto_refund);
+			Self::deposit_event(RawEvent::AutoRefundSet(auto_refund));
+		}
+
+		/// Claim a failed deposit of the sender into the account `dest`.
+		///
+		/// The deposit is made as when the transfer was received, so an unknown asset must be
+		/// registered first. Nothing is changed if the deposit fails.
+		#[weight = 100_000_000 + T::DbWeight::get().reads_writes(6, 5)]
+		#[transactional]
+		pub fn claim(origin, deposit_id: DepositId, dest: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			let FailedDeposit { origin: para_id, asset_id, amount } =
+				Self::failed_deposit(&who, deposit_id).ok_or(Error::<T>::UnknownDeposit)?;
+
+			match asset_id {
+				Some(asset_id) => Self::do_receive(para_id, asset_id, dest.clone(), amount)?,
+				None => Self::deposit_native(&dest, amount)?,
+			}
+
+			<FailedDeposits<T>>::remove(&who, deposit_id);
+			Self::deposit_event(RawEvent::DepositClaimed(deposit_id, dest));
+		}
+
+		/// Send a failed deposit of the sender back to the sender on the parachain it came from.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn refund(origin, deposit_id: DepositId) {
+			let who = ensure_signed(origin)?;
+			let deposit = Self::failed_deposit(&who, deposit_id).ok_or(Error::<T>::UnknownDeposit)?;
+
+			Self::send_back(deposit.origin, deposit.asset_id, who.clone(), deposit.amount)?;
+			<FailedDeposits<T>>::remove(&who, deposit_id);
+		}
+
+		/// Check the reserves of an asset against its total supply and the sovereign accounts.
+		///
+		/// The result is reported by a `Reconciled` event, and is the same as
+		/// `reconcile_reserves` of the runtime API returns.
+		#[weight = 50_000_000 + T::DbWeight::get().reads(10)]
+		pub fn reconcile(origin, asset_id: AssetIdOf<T>) {
+			ensure_root(origin)?;
+
+			let reconciliation = Self::reconcile_reserves(asset_id);
+			Self::deposit_event(RawEvent::Reconciled(
+				asset_id,
+				reconciliation.total_supply,
+				reconciliation.shortfall,
+			));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The sovereign account of a parachain, holding the reserves of the assets sent to it.
+	pub fn sovereign_account(para_id: ParaId) -> T::AccountId {
+		para_id.into_account()
+	}
+
+	/// Remove `amount` of a local asset from `who` according to `mode`.
+	fn take(
+		mode: TransferMode,
+		para_id: ParaId,
+		asset_id: AssetIdOf<T>,
+		who: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		match mode {
+			TransferMode::BurnMint => AssetsOf::<T>::withdraw(asset_id, who, amount),
+			TransferMode::Reserve => {
+				AssetsOf::<T>::transfer(asset_id, who, &Self::sovereign_account(para_id), amount)
+			}
+		}
+	}
+
+	/// Give back an amount taken from `who` for a message that could not be sent.
+	fn give_back(
+		para_id: ParaId,
+		asset_id: AssetIdOf<T>,
+		who: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		match Self::transfer_mode(asset_id) {
+			Some(TransferMode::Reserve)
+				if AssetRegistry::<T>::foreign_asset(asset_id).is_none() =>
+			{
+				AssetsOf::<T>::transfer(asset_id, &Self::sovereign_account(para_id), who, amount)
+			}
+			_ => AssetsOf::<T>::deposit(asset_id, who, amount),
+		}
+	}
+
+	fn mutate_reserve(
+		asset_id: AssetIdOf<T>,
+		para_id: ParaId,
+		f: impl FnOnce(AssetBalanceOf<T>) -> AssetBalanceOf<T>,
+	) {
+		let reserve = f(Self::reserve(asset_id, para_id));
+		if reserve.is_zero() {
+			<Reserves<T>>::remove(asset_id, para_id);
+		} else {
+			<Reserves<T>>::insert(asset_id, para_id, reserve);
+		}
+		Self::deposit_event(RawEvent::ReserveUpdated(asset_id, para_id, reserve));
+	}
+
+	fn mutate_backing(
+		asset_id: AssetIdOf<T>,
+		f: impl FnOnce(AssetBalanceOf<T>) -> AssetBalanceOf<T>,
+	) {
+		let backing = f(Self::backing(asset_id));
+		if backing.is_zero() {
+			<Backing<T>>::remove(asset_id);
+		} else {
+			<Backing<T>>::insert(asset_id, backing);
+		}
+		Self::deposit_event(RawEvent::BackingUpdated(asset_id, backing));
+	}
+
+	/// Check the reserves of an asset against its total supply and the sovereign accounts.
+	///
+	/// The total supply of a foreign asset must not exceed its backing. The reserves of a local
+	/// asset in `Reserve` mode must be held by the sovereign accounts of the parachains.
+	pub fn reconcile_reserves(asset_id: AssetIdOf<T>) -> ReserveReconciliation<AssetBalanceOf<T>> {
+		let total_supply = AssetsOf::<T>::total_supply(asset_id);
+		let reserves = <Reserves<T>>::iter_prefix(asset_id).collect::<Vec<_>>();
+
+		let (backing, shortfall) = if AssetRegistry::<T>::foreign_asset(asset_id).is_some() {
+			let backing = Self::backing(asset_id);
+			(Some(backing), total_supply.saturating_sub(backing))
+		} else if Self::transfer_mode(asset_id) == Some(TransferMode::Reserve) {
+			let mut missing = AssetBalanceOf::<T>::zero();
+			for (para_id, reserve) in &reserves {
+				let held = AssetsOf::<T>::balance(asset_id, &Self::sovereign_account(*para_id));
+				missing = missing.saturating_add(reserve.saturating_sub(held));
+			}
+			(None, missing)
+		} else {
+			(None, Zero::zero())
+		};
+
+		ReserveReconciliation { total_supply, reserves, backing, shortfall }
+	}
+
+	/// Handle `amount` of the asset `asset_id` received from `para_id` for the account `dest`.
+	///
+	/// A transfer that cannot be deposited is sent back or kept as a failed deposit.
+	pub fn receive(
+		para_id: ParaId,
+		asset_id: AssetIdOf<T>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) {
+		if T::DepositFilter::admit(para_id, Some(asset_id), &dest, amount) {
+			Self::deposit_admitted(para_id, Some(asset_id), dest, amount);
+		}
+	}
+
+	/// Whether `amount` of the native token received from `para_id` for `dest` may be deposited
+	/// by `TokenDealer`. Otherwise it was taken over by the filter or kept as a failed deposit.
+	pub fn receive_native(para_id: ParaId, dest: &T::AccountId, amount: AssetBalanceOf<T>) -> bool {
+		if !T::DepositFilter::admit(para_id, None, dest, amount) {
+			return false;
+		}
+		if !Self::can_deposit_native(dest, amount) {
+			Self::deposit_failed(para_id, None, dest.clone(), amount);
+			return false;
+		}
+		true
+	}
+
+	/// Deposit a transfer from `para_id` without asking the filter, e.g. one the filter held
+	/// back. A transfer that cannot be deposited is sent back or kept as a failed deposit.
+	pub fn deposit_admitted(
+		para_id: ParaId,
+		asset_id: Option<AssetIdOf<T>>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) {
+		let result = match asset_id {
+			Some(asset_id) => Self::do_receive(para_id, asset_id, dest.clone(), amount),
+			None => Self::deposit_native(&dest, amount),
+		};
+		if result.is_err() {
+			Self::deposit_failed(para_id, asset_id, dest, amount);
+		}
+	}
+
+	/// The local asset a transfer of `asset_id` from `para_id` is deposited as, if known: the
+	/// registered asset of the parachain or a transferable local asset coming back.
+	pub fn local_asset(para_id: ParaId, asset_id: AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+		AssetRegistry::<T>::local_asset_id(para_id, asset_id)
+			.or_else(|| Self::transfer_mode(asset_id).map(|_| asset_id))
+	}
+
+	/// Whether `amount` of the native token can be deposited into `who`.
+	pub fn can_deposit_native(who: &T::AccountId, amount: AssetBalanceOf<T>) -> bool {
+		T::Currency::free_balance(who).saturating_add(amount) >= T::Currency::minimum_balance()
+	}
+
+	/// Keep a transfer from `para_id` that could not be deposited into `dest`, or send it back if
+	/// `AutoRefund` is set, transfers to other chains are not paused and the message can be sent.
+	pub fn deposit_failed(
+		para_id: ParaId,
+		asset_id: Option<AssetIdOf<T>>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) {
+		if Self::auto_refund() && Self::send_back(para_id, asset_id, dest.clone(), amount).is_ok() {
+			return;
+		}
+
+		let deposit_id = NextDepositId::mutate(|id| {
+			let deposit_id = *id;
+			*id = id.wrapping_add(1);
+			deposit_id
+		});
+		<FailedDeposits<T>>::insert(&dest, deposit_id, FailedDeposit {
+			origin: para_id,
+			asset_id,
+			amount,
+		});
+		Self::deposit_event(RawEvent::DepositFailed(deposit_id, para_id, asset_id, dest, amount));
+	}
+
+	/// Send a transfer that could not be deposited back to `who` on `para_id`, unless transfers
+	/// to other chains are paused.
+	pub fn send_back(
+		para_id: ParaId,
+		asset_id: Option<AssetIdOf<T>>,
+		who: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(!T::OutboundPaused::get(), Error::<T>::OutboundPaused);
+
+		let msg = XCMPMessage::TransferToken(who.clone(), amount, asset_id);
+		T::XCMPMessageSender::send_xcmp_message(para_id, &msg)
+			.map_err(|_| Error::<T>::SendFailed)?;
+
+		Self::deposit_event(RawEvent::DepositRefunded(para_id, asset_id, who, amount));
+		Ok(())
+	}
+
+	fn deposit_native(who: &T::AccountId, amount: AssetBalanceOf<T>) -> DispatchResult {
+		ensure!(Self::can_deposit_native(who, amount), Error::<T>::BelowMinimumBalance);
+
+		let _ = T::Currency::deposit_creating(who, amount);
+		Ok(())
+	}
+
+	/// Deposit a transfer from `para_id`: assets registered for the parachain are minted as their
+	/// local asset, otherwise a transferable local asset is coming back. Anything else is handed
+	/// to the registry policy.
+	fn do_receive(
+		para_id: ParaId,
+		asset_id: AssetIdOf<T>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let mode = match Self::transfer_mode(asset_id) {
+			Some(mode) if AssetRegistry::<T>::local_asset_id(para_id, asset_id).is_none() => mode,
+			_ => return Self::receive_foreign(para_id, asset_id, dest, amount),
+		};
+
+		let result = Self::release(mode, para_id, asset_id, &dest, amount);
+		match result {
+			Ok(()) => {
+				Self::mutate_reserve(asset_id, para_id, |reserve| reserve - amount);
+				Self::deposit_event(RawEvent::Returned(para_id, asset_id, dest, amount));
+			}
+			Err(_) => Self::deposit_event(RawEvent::ReturnFailed(para_id, asset_id, dest, amount)),
+		}
+		result
+	}
+
+	/// Give `amount` of a local asset that came back from `para_id` to `dest` according to
+	/// `mode`, if it does not exceed the reserve of the parachain.
+	fn release(
+		mode: TransferMode,
+		para_id: ParaId,
+		asset_id: AssetIdOf<T>,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		ensure!(amount <= Self::reserve(asset_id, para_id), Error::<T>::InsufficientReserve);
+
+		match mode {
+			TransferMode::BurnMint => AssetsOf::<T>::deposit(asset_id, dest, amount),
+			TransferMode::Reserve => {
+				AssetsOf::<T>::transfer(asset_id, &Self::sovereign_account(para_id), dest, amount)
+			}
+		}
+	}
+
+	/// Mint an asset of `para_id` as its local asset and add the amount to its backing.
+	fn receive_foreign(
+		para_id: ParaId,
+		remote_id: AssetIdOf<T>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> DispatchResult {
+		let local_id = AssetRegistry::<T>::receive(para_id, remote_id, dest, amount)?;
+		Self::mutate_backing(local_id, |backing| backing.saturating_add(amount));
+		Ok(())
+	}
+}
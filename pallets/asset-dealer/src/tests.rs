@@ -0,0 +1,519 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of failed deposits and their refunds and claims, and of the reserve reconciliation.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types, weights::Weight,
+};
+use frame_system::RawOrigin;
+use pallet_asset_registry::AssetIdAllocator;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+use std::{cell::RefCell, collections::HashMap};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod asset_dealer {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		pallet_asset_registry<T>,
+		asset_dealer<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 10;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static ASSETS: RefCell<HashMap<(u32, u64), u64>> = RefCell::new(HashMap::new());
+	static NEXT_ASSET_ID: RefCell<u32> = RefCell::new(100);
+	static SENT: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+	static SEND_FAILS: RefCell<bool> = RefCell::new(false);
+	static OUTBOUND_PAUSED: RefCell<bool> = RefCell::new(false);
+}
+
+/// Assets kept in a map. The total supply is the sum of the balances.
+pub struct TestAssets;
+
+impl MultiAsset<u64> for TestAssets {
+	type AssetId = u32;
+	type Balance = u64;
+
+	fn balance(asset_id: u32, who: &u64) -> u64 {
+		ASSETS.with(|a| a.borrow().get(&(asset_id, *who)).copied().unwrap_or_default())
+	}
+
+	fn total_supply(asset_id: u32) -> u64 {
+		ASSETS.with(|a| {
+			a.borrow().iter().filter(|((id, _), _)| *id == asset_id).map(|(_, b)| *b).sum()
+		})
+	}
+
+	fn transfer(asset_id: u32, from: &u64, to: &u64, amount: u64) -> DispatchResult {
+		Self::withdraw(asset_id, from, amount)?;
+		Self::deposit(asset_id, to, amount)
+	}
+
+	fn deposit(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who) + amount;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+
+	fn withdraw(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who)
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Insufficient asset balance"))?;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+}
+
+/// Hands out increasing asset ids.
+pub struct NextAssetId;
+
+impl AssetIdAllocator<u32> for NextAssetId {
+	fn allocate() -> u32 {
+		NEXT_ASSET_ID.with(|next| {
+			let id = *next.borrow();
+			*next.borrow_mut() += 1;
+			id
+		})
+	}
+
+	fn is_allocated(asset_id: u32) -> bool {
+		NEXT_ASSET_ID.with(|next| asset_id < *next.borrow())
+	}
+
+	fn reserve(asset_id: u32) {
+		NEXT_ASSET_ID.with(|next| {
+			let mut next = next.borrow_mut();
+			*next = (*next).max(asset_id + 1);
+		})
+	}
+}
+
+/// Records the sent messages, or fails to send them while `SEND_FAILS` is set.
+pub struct TestSender;
+
+impl XCMPMessageSender<XCMPMessage<u64, u64, u32>> for TestSender {
+	fn send_xcmp_message(dest: ParaId, msg: &XCMPMessage<u64, u64, u32>) -> Result<(), ()> {
+		if SEND_FAILS.with(|fails| *fails.borrow()) {
+			return Err(());
+		}
+		SENT.with(|sent| sent.borrow_mut().push((dest, msg.encode())));
+		Ok(())
+	}
+}
+
+/// `true` while `OUTBOUND_PAUSED` is set.
+pub struct OutboundPaused;
+
+impl Get<bool> for OutboundPaused {
+	fn get() -> bool {
+		OUTBOUND_PAUSED.with(|paused| *paused.borrow())
+	}
+}
+
+impl pallet_asset_registry::Trait for Test {
+	type Event = TestEvent;
+	type Assets = TestAssets;
+	type AssetIdAllocator = NextAssetId;
+	type RegisterOrigin = frame_system::EnsureRoot<u64>;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type XCMPMessageSender = TestSender;
+	type Currency = Balances;
+	type UpdateOrigin = frame_system::EnsureRoot<u64>;
+	type DepositFilter = ();
+	type OutboundPaused = OutboundPaused;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type Registry = pallet_asset_registry::Module<Test>;
+type AssetDealer = Module<Test>;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const SIBLING: u32 = 200;
+const REMOTE_ASSET: u32 = 7;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	ASSETS.with(|a| a.borrow_mut().clear());
+	NEXT_ASSET_ID.with(|next| *next.borrow_mut() = 100);
+	SENT.with(|sent| sent.borrow_mut().clear());
+	SEND_FAILS.with(|fails| *fails.borrow_mut() = false);
+	OUTBOUND_PAUSED.with(|paused| *paused.borrow_mut() = false);
+
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(BOB, 100)] }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn sibling() -> ParaId {
+	ParaId::from(SIBLING)
+}
+
+fn sent() -> Vec<(ParaId, Vec<u8>)> {
+	SENT.with(|sent| sent.borrow().clone())
+}
+
+fn transfer_message(who: u64, amount: u64, asset_id: Option<u32>) -> Vec<u8> {
+	XCMPMessage::<u64, u64, u32>::TransferToken(who, amount, asset_id).encode()
+}
+
+fn events() -> Vec<TestEvent> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
+
+fn set_send_fails(fails: bool) {
+	SEND_FAILS.with(|f| *f.borrow_mut() = fails);
+}
+
+fn set_outbound_paused(paused: bool) {
+	OUTBOUND_PAUSED.with(|p| *p.borrow_mut() = paused);
+}
+
+#[test]
+fn unknown_asset_is_kept_as_failed_deposit() {
+	new_test_ext().execute_with(|| {
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_eq!(
+			AssetDealer::failed_deposit(ALICE, 0),
+			Some(FailedDeposit { origin: sibling(), asset_id: Some(REMOTE_ASSET), amount: 50 }),
+		);
+		let failed = RawEvent::DepositFailed(0, sibling(), Some(REMOTE_ASSET), ALICE, 50);
+		assert!(events().contains(&TestEvent::asset_dealer(failed)));
+	});
+}
+
+#[test]
+fn failed_deposit_is_claimed_once_the_asset_is_registered() {
+	new_test_ext().execute_with(|| {
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_noop!(
+			AssetDealer::claim(Origin::signed(ALICE), 0, BOB),
+			pallet_asset_registry::Error::<Test>::NotRegistered,
+		);
+
+		assert_ok!(Registry::register(RawOrigin::Root.into(), sibling(), REMOTE_ASSET, Some(3)));
+		assert_ok!(AssetDealer::claim(Origin::signed(ALICE), 0, BOB));
+
+		assert_eq!(TestAssets::balance(3, &BOB), 50);
+		assert_eq!(AssetDealer::backing(3), 50);
+		assert_eq!(AssetDealer::failed_deposit(ALICE, 0), None);
+		assert_noop!(
+			AssetDealer::claim(Origin::signed(ALICE), 0, BOB),
+			Error::<Test>::UnknownDeposit,
+		);
+	});
+}
+
+#[test]
+fn register_requires_an_issued_local_asset() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Registry::register(RawOrigin::Root.into(), sibling(), REMOTE_ASSET, Some(100)),
+			pallet_asset_registry::Error::<Test>::UnknownLocalAsset,
+		);
+
+		assert_ok!(Registry::register(RawOrigin::Root.into(), sibling(), REMOTE_ASSET, None));
+		assert_eq!(Registry::local_asset_id(sibling(), REMOTE_ASSET), Some(100));
+	});
+}
+
+#[test]
+fn native_deposit_below_existential_deposit_is_claimed_into_funded_account() {
+	new_test_ext().execute_with(|| {
+		assert!(!AssetDealer::receive_native(sibling(), &ALICE, 5));
+		assert_eq!(
+			AssetDealer::failed_deposit(ALICE, 0),
+			Some(FailedDeposit { origin: sibling(), asset_id: None, amount: 5 }),
+		);
+
+		assert_noop!(
+			AssetDealer::claim(Origin::signed(ALICE), 0, ALICE),
+			Error::<Test>::BelowMinimumBalance,
+		);
+		assert_ok!(AssetDealer::claim(Origin::signed(ALICE), 0, BOB));
+		assert_eq!(Balances::free_balance(BOB), 105);
+	});
+}
+
+#[test]
+fn failed_claim_of_a_returning_asset_changes_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_transfer_mode(
+			RawOrigin::Root.into(),
+			1,
+			Some(TransferMode::BurnMint),
+		));
+		AssetDealer::receive(sibling(), 1, ALICE, 50);
+		assert!(AssetDealer::failed_deposit(ALICE, 0).is_some());
+
+		// The release fails after the `ReturnFailed` event, which is rolled back with the claim.
+		assert_noop!(
+			AssetDealer::claim(Origin::signed(ALICE), 0, BOB),
+			Error::<Test>::InsufficientReserve,
+		);
+	});
+}
+
+#[test]
+fn only_the_beneficiary_can_claim() {
+	new_test_ext().execute_with(|| {
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_noop!(
+			AssetDealer::claim(Origin::signed(BOB), 0, BOB),
+			Error::<Test>::UnknownDeposit,
+		);
+		assert_noop!(AssetDealer::refund(Origin::signed(BOB), 0), Error::<Test>::UnknownDeposit);
+	});
+}
+
+#[test]
+fn refund_sends_the_deposit_back() {
+	new_test_ext().execute_with(|| {
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_ok!(AssetDealer::refund(Origin::signed(ALICE), 0));
+
+		assert_eq!(sent(), vec![(sibling(), transfer_message(ALICE, 50, Some(REMOTE_ASSET)))]);
+		assert_eq!(AssetDealer::failed_deposit(ALICE, 0), None);
+	});
+}
+
+#[test]
+fn failed_refund_keeps_the_deposit() {
+	new_test_ext().execute_with(|| {
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+		set_send_fails(true);
+
+		assert_noop!(AssetDealer::refund(Origin::signed(ALICE), 0), Error::<Test>::SendFailed);
+		assert!(AssetDealer::failed_deposit(ALICE, 0).is_some());
+	});
+}
+
+#[test]
+fn auto_refund_sends_failed_deposits_back_right_away() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_auto_refund(RawOrigin::Root.into(), true));
+
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_eq!(sent(), vec![(sibling(), transfer_message(ALICE, 50, Some(REMOTE_ASSET)))]);
+		assert_eq!(AssetDealer::failed_deposit(ALICE, 0), None);
+	});
+}
+
+#[test]
+fn auto_refund_keeps_the_deposit_if_it_cannot_be_sent() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_auto_refund(RawOrigin::Root.into(), true));
+		set_send_fails(true);
+
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert!(sent().is_empty());
+		assert!(AssetDealer::failed_deposit(ALICE, 0).is_some());
+	});
+}
+
+#[test]
+fn auto_refund_keeps_the_deposit_while_outbound_is_paused() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_auto_refund(RawOrigin::Root.into(), true));
+		set_outbound_paused(true);
+
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert!(sent().is_empty());
+		assert!(AssetDealer::failed_deposit(ALICE, 0).is_some());
+		assert_noop!(AssetDealer::refund(Origin::signed(ALICE), 0), Error::<Test>::OutboundPaused);
+
+		set_outbound_paused(false);
+		assert_ok!(AssetDealer::refund(Origin::signed(ALICE), 0));
+		assert_eq!(sent(), vec![(sibling(), transfer_message(ALICE, 50, Some(REMOTE_ASSET)))]);
+	});
+}
+
+#[test]
+fn nothing_is_sent_while_outbound_is_paused() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_transfer_mode(
+			RawOrigin::Root.into(),
+			1,
+			Some(TransferMode::Reserve),
+		));
+		TestAssets::deposit(1, &ALICE, 100).unwrap();
+		set_outbound_paused(true);
+
+		assert_noop!(
+			AssetDealer::transfer_to_parachain(Origin::signed(ALICE), sibling(), BOB, 1, 40),
+			Error::<Test>::OutboundPaused,
+		);
+		assert!(sent().is_empty());
+	});
+}
+
+#[test]
+fn failed_send_gives_the_asset_back() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_transfer_mode(
+			RawOrigin::Root.into(),
+			1,
+			Some(TransferMode::Reserve),
+		));
+		TestAssets::deposit(1, &ALICE, 100).unwrap();
+		set_send_fails(true);
+
+		assert_noop!(
+			AssetDealer::transfer_to_parachain(Origin::signed(ALICE), sibling(), BOB, 1, 40),
+			Error::<Test>::SendFailed,
+		);
+		assert_eq!(TestAssets::balance(1, &ALICE), 100);
+		assert_eq!(AssetDealer::reserve(1, sibling()), 0);
+	});
+}
+
+#[test]
+fn reconcile_reports_foreign_supply_above_the_backing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Registry::register(RawOrigin::Root.into(), sibling(), REMOTE_ASSET, Some(3)));
+		AssetDealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_eq!(
+			AssetDealer::reconcile_reserves(3),
+			ReserveReconciliation {
+				total_supply: 50,
+				reserves: vec![],
+				backing: Some(50),
+				shortfall: 0,
+			},
+		);
+
+		TestAssets::deposit(3, &BOB, 20).unwrap();
+		assert_ok!(AssetDealer::reconcile(RawOrigin::Root.into(), 3));
+
+		assert_eq!(AssetDealer::reconcile_reserves(3).shortfall, 20);
+		assert_eq!(
+			events().pop(),
+			Some(TestEvent::asset_dealer(RawEvent::Reconciled(3, 70, 20))),
+		);
+	});
+}
+
+#[test]
+fn reconcile_reports_reserves_missing_from_the_sovereign_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetDealer::set_transfer_mode(
+			RawOrigin::Root.into(),
+			1,
+			Some(TransferMode::Reserve),
+		));
+		TestAssets::deposit(1, &ALICE, 100).unwrap();
+		assert_ok!(AssetDealer::transfer_to_parachain(
+			Origin::signed(ALICE),
+			sibling(),
+			BOB,
+			1,
+			40,
+		));
+
+		assert_eq!(
+			AssetDealer::reconcile_reserves(1),
+			ReserveReconciliation {
+				total_supply: 100,
+				reserves: vec![(sibling(), 40)],
+				backing: None,
+				shortfall: 0,
+			},
+		);
+
+		let sovereign = AssetDealer::sovereign_account(sibling());
+		TestAssets::withdraw(1, &sovereign, 15).unwrap();
+
+		assert_eq!(
+			AssetDealer::reconcile_reserves(1),
+			ReserveReconciliation {
+				total_supply: 85,
+				reserves: vec![(sibling(), 40)],
+				backing: None,
+				shortfall: 15,
+			},
+		);
+		assert_noop!(AssetDealer::reconcile(Origin::signed(ALICE), 1), BadOrigin);
+	});
+}
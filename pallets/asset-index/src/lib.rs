@@ -0,0 +1,55 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that indexes which assets every account holds.
+//!
+//! An asset is in the index of an account while the account has a balance of it. The runtime
+//! notes every balance it changes through `note`, in the call that changes it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{decl_module, decl_storage, IterableStorageDoubleMap};
+use parachain_primitives::MultiAsset;
+use sp_runtime::traits::Zero;
+use sp_std::prelude::*;
+
+type AssetIdOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The assets to index.
+	type Assets: MultiAsset<Self::AccountId>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetIndex {
+		/// The assets every account holds.
+		AccountAssets: double_map hasher(blake2_128_concat) T::AccountId,
+			hasher(twox_64_concat) AssetIdOf<T> => ();
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+}
+
+impl<T: Trait> Module<T> {
+	/// Add the asset to or remove it from the index of `who`, depending on its balance.
+	pub fn note(asset_id: AssetIdOf<T>, who: &T::AccountId) {
+		if T::Assets::balance(asset_id, who).is_zero() {
+			<AccountAssets<T>>::remove(who, asset_id);
+		} else {
+			<AccountAssets<T>>::insert(who, asset_id, ());
+		}
+	}
+
+	/// The assets `who` holds and its balance of each.
+	pub fn account_assets(who: &T::AccountId) -> Vec<(AssetIdOf<T>, AssetBalanceOf<T>)> {
+		<AccountAssets<T>>::iter_prefix(who)
+			.map(|(asset_id, ())| (asset_id, T::Assets::balance(asset_id, who)))
+			.filter(|(_, balance)| !balance.is_zero())
+			.collect()
+	}
+}
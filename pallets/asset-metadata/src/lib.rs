@@ -0,0 +1,265 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that stores the name, symbol, decimals and logo of the `pallet_assets` assets.
+//!
+//! The owner of an asset is the account that issued it, which the runtime reports through
+//! `note_issued`, or the one set by `ForceOrigin`. The owner may set the metadata of the asset by
+//! reserving `MetadataDepositBase` plus `MetadataDepositPerByte` for every byte of the name and
+//! symbol. `ForceOrigin` may set metadata without a deposit.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchResult,
+	ensure,
+	traits::{Currency, EnsureOrigin, Get, ReservableCurrency},
+};
+use frame_system::ensure_signed;
+use parachain_primitives::{AssetMetadata, MultiAsset};
+use sp_core::H256;
+use sp_runtime::traits::Saturating;
+use sp_std::prelude::*;
+
+type AssetIdOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The assets to describe.
+	type Assets: MultiAsset<Self::AccountId>;
+
+	/// The currency metadata deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// Origin that may set owners and metadata of any asset.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Amount reserved for the metadata of an asset.
+	type MetadataDepositBase: Get<BalanceOf<Self>>;
+
+	/// Amount reserved for every byte of the name and symbol of an asset.
+	type MetadataDepositPerByte: Get<BalanceOf<Self>>;
+
+	/// Maximum length of the name and of the symbol.
+	type StringLimit: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetMetadata {
+		/// The owner of every asset.
+		Owners get(fn owner): map hasher(twox_64_concat) AssetIdOf<T> => Option<T::AccountId>;
+
+		/// The metadata of every asset.
+		Metadata get(fn metadata): map hasher(twox_64_concat) AssetIdOf<T> => Option<AssetMetadata>;
+
+		/// The account that reserved the deposit for the metadata of an asset, and the amount.
+		Deposits get(fn deposit):
+			map hasher(twox_64_concat) AssetIdOf<T> => Option<(T::AccountId, BalanceOf<T>)>;
+	}
+	add_extra_genesis {
+		/// Assets with their owner, name, symbol and decimals.
+		config(assets): Vec<(AssetIdOf<T>, T::AccountId, Vec<u8>, Vec<u8>, u8)>;
+		build(|config| {
+			for (asset_id, owner, name, symbol, decimals) in &config.assets {
+				assert!(
+					name.len() as u32 <= T::StringLimit::get()
+						&& symbol.len() as u32 <= T::StringLimit::get(),
+					"Genesis asset name or symbol exceeds `StringLimit`",
+				);
+
+				<Owners<T>>::insert(asset_id, owner);
+				<Metadata<T>>::insert(asset_id, AssetMetadata {
+					name: name.clone(),
+					symbol: symbol.clone(),
+					decimals: *decimals,
+					logo: None,
+				});
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+	{
+		/// The owner of an asset was set.
+		OwnerSet(AssetId, AccountId),
+		/// The metadata of an asset was set.
+		MetadataSet(AssetId, Vec<u8>, Vec<u8>, u8, Option<H256>),
+		/// The metadata of an asset was removed.
+		MetadataCleared(AssetId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The sender does not own the asset.
+		NotOwner,
+		/// The name or the symbol is longer than `StringLimit`.
+		BadMetadata,
+		/// The asset has no metadata.
+		NoMetadata,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Amount reserved for the metadata of an asset.
+		const MetadataDepositBase: BalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// Amount reserved for every byte of the name and symbol of an asset.
+		const MetadataDepositPerByte: BalanceOf<T> = T::MetadataDepositPerByte::get();
+
+		/// Maximum length of the name and of the symbol.
+		const StringLimit: u32 = T::StringLimit::get();
+
+		/// Set the metadata of an asset owned by the sender, reserving the deposit for it.
+		///
+		/// The deposit of previously set metadata is adjusted to the new size.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(3, 2)]
+		pub fn set_metadata(
+			origin,
+			asset_id: AssetIdOf<T>,
+			name: Vec<u8>,
+			symbol: Vec<u8>,
+			decimals: u8,
+			logo: Option<H256>,
+		) {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::owner(asset_id).as_ref() == Some(&who), Error::<T>::NotOwner);
+			Self::ensure_within_limit(&name, &symbol)?;
+
+			let deposit = T::MetadataDepositPerByte::get()
+				.saturating_mul(((name.len() + symbol.len()) as u32).into())
+				.saturating_add(T::MetadataDepositBase::get());
+			match Self::deposit(asset_id) {
+				Some((depositor, old)) if depositor == who => {
+					if deposit > old {
+						T::Currency::reserve(&who, deposit - old)?;
+					} else {
+						T::Currency::unreserve(&who, old - deposit);
+					}
+				}
+				previous => {
+					T::Currency::reserve(&who, deposit)?;
+					if let Some((depositor, old)) = previous {
+						T::Currency::unreserve(&depositor, old);
+					}
+				}
+			}
+			<Deposits<T>>::insert(asset_id, (&who, deposit));
+
+			Self::do_set_metadata(asset_id, AssetMetadata { name, symbol, decimals, logo });
+		}
+
+		/// Remove the metadata of an asset owned by the sender and return the deposit.
+		#[weight = 30_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn clear_metadata(origin, asset_id: AssetIdOf<T>) {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::owner(asset_id).as_ref() == Some(&who), Error::<T>::NotOwner);
+
+			Self::do_clear_metadata(asset_id)?;
+		}
+
+		/// Give an asset owned by the sender to `new_owner`.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+		pub fn transfer_ownership(origin, asset_id: AssetIdOf<T>, new_owner: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::owner(asset_id).as_ref() == Some(&who), Error::<T>::NotOwner);
+
+			<Owners<T>>::insert(asset_id, &new_owner);
+			Self::deposit_event(RawEvent::OwnerSet(asset_id, new_owner));
+		}
+
+		/// Set the owner of an asset, e.g. of one issued before this pallet was added.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn force_set_owner(origin, asset_id: AssetIdOf<T>, owner: T::AccountId) {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			<Owners<T>>::insert(asset_id, &owner);
+			Self::deposit_event(RawEvent::OwnerSet(asset_id, owner));
+		}
+
+		/// Set the metadata of any asset without a deposit. A previous deposit is returned.
+		#[weight = 30_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+		pub fn force_set_metadata(
+			origin,
+			asset_id: AssetIdOf<T>,
+			name: Vec<u8>,
+			symbol: Vec<u8>,
+			decimals: u8,
+			logo: Option<H256>,
+		) {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::ensure_within_limit(&name, &symbol)?;
+
+			if let Some((depositor, deposit)) = <Deposits<T>>::take(asset_id) {
+				T::Currency::unreserve(&depositor, deposit);
+			}
+			Self::do_set_metadata(asset_id, AssetMetadata { name, symbol, decimals, logo });
+		}
+
+		/// Remove the metadata of any asset and return the deposit.
+		#[weight = 30_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn force_clear_metadata(origin, asset_id: AssetIdOf<T>) {
+			T::ForceOrigin::ensure_origin(origin)?;
+
+			Self::do_clear_metadata(asset_id)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Make `issuer` the owner of a newly issued asset, unless it already has one.
+	pub fn note_issued(asset_id: AssetIdOf<T>, issuer: &T::AccountId) {
+		if !<Owners<T>>::contains_key(asset_id) {
+			<Owners<T>>::insert(asset_id, issuer);
+			Self::deposit_event(RawEvent::OwnerSet(asset_id, issuer.clone()));
+		}
+	}
+
+	fn ensure_within_limit(name: &[u8], symbol: &[u8]) -> DispatchResult {
+		ensure!(
+			name.len() as u32 <= T::StringLimit::get()
+				&& symbol.len() as u32 <= T::StringLimit::get(),
+			Error::<T>::BadMetadata,
+		);
+		Ok(())
+	}
+
+	fn do_set_metadata(asset_id: AssetIdOf<T>, metadata: AssetMetadata) {
+		Self::deposit_event(RawEvent::MetadataSet(
+			asset_id,
+			metadata.name.clone(),
+			metadata.symbol.clone(),
+			metadata.decimals,
+			metadata.logo,
+		));
+		<Metadata<T>>::insert(asset_id, metadata);
+	}
+
+	fn do_clear_metadata(asset_id: AssetIdOf<T>) -> DispatchResult {
+		ensure!(<Metadata<T>>::contains_key(asset_id), Error::<T>::NoMetadata);
+
+		<Metadata<T>>::remove(asset_id);
+		if let Some((depositor, deposit)) = <Deposits<T>>::take(asset_id) {
+			T::Currency::unreserve(&depositor, deposit);
+		}
+
+		Self::deposit_event(RawEvent::MetadataCleared(asset_id));
+		Ok(())
+	}
+}
@@ -0,0 +1,248 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that maps the assets of sibling parachains to local `pallet_assets` assets.
+//!
+//! Every asset received over XCMP is identified by the sending parachain and its id on that
+//! parachain. Registered pairs are minted as their local asset. Unknown pairs get a new local
+//! asset or are rejected, according to `Policy`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives::ParaId;
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchError,
+	ensure,
+	traits::{EnsureOrigin, Get},
+};
+use parachain_primitives::MultiAsset;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+type AssetIdOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// What to do with assets of sibling parachains that are not registered.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum RegistrationPolicy {
+	/// Register them under a new local asset.
+	AutoCreate,
+	/// Reject them.
+	Reject,
+}
+
+impl Default for RegistrationPolicy {
+	fn default() -> Self {
+		RegistrationPolicy::Reject
+	}
+}
+
+/// Source of new local asset ids.
+pub trait AssetIdAllocator<AssetId> {
+	/// An asset id that is not used yet.
+	fn allocate() -> AssetId;
+
+	/// Whether `asset_id` was already handed out, by `allocate` or by issuing an asset.
+	fn is_allocated(asset_id: AssetId) -> bool;
+
+	/// Make sure `allocate` never hands out `asset_id`.
+	fn reserve(asset_id: AssetId);
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The local assets.
+	type Assets: MultiAsset<Self::AccountId>;
+
+	/// Allocates the local ids of new foreign assets.
+	type AssetIdAllocator: AssetIdAllocator<AssetIdOf<Self>>;
+
+	/// Origin that may register assets and change the policy.
+	type RegisterOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetRegistry {
+		/// The local asset of every registered asset of a sibling parachain.
+		LocalAssetIds get(fn local_asset_id): double_map hasher(twox_64_concat) ParaId,
+			hasher(twox_64_concat) AssetIdOf<T> => Option<AssetIdOf<T>>;
+
+		/// The sibling parachain and remote id of every local asset that represents a foreign one.
+		ForeignAssets get(fn foreign_asset):
+			map hasher(twox_64_concat) AssetIdOf<T> => Option<(ParaId, AssetIdOf<T>)>;
+
+		/// What to do with assets of sibling parachains that are not registered.
+		Policy get(fn policy) config(): RegistrationPolicy;
+	}
+	add_extra_genesis {
+		/// Sibling parachains, the ids of assets on them and the local ids of these assets.
+		config(foreign_assets): Vec<(ParaId, AssetIdOf<T>, AssetIdOf<T>)>;
+		build(|config| {
+			for (para_id, remote_id, local_id) in &config.foreign_assets {
+				assert!(
+					!<ForeignAssets<T>>::contains_key(local_id),
+					"Local asset registered twice in genesis",
+				);
+
+				T::AssetIdAllocator::reserve(*local_id);
+				<LocalAssetIds<T>>::insert(para_id, remote_id, local_id);
+				<ForeignAssets<T>>::insert(local_id, (para_id, remote_id));
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+		AssetBalance = AssetBalanceOf<T>,
+	{
+		/// An asset of a sibling parachain was registered as the given local asset.
+		Registered(ParaId, AssetId, AssetId),
+		/// An asset of a sibling parachain was deregistered.
+		Deregistered(ParaId, AssetId),
+		/// The registration policy was changed.
+		PolicySet(RegistrationPolicy),
+		/// An asset was received from a sibling parachain and minted as the given local asset.
+		Received(ParaId, AssetId, AccountId, AssetBalance),
+		/// An asset was received from a sibling parachain that is not registered, and dropped.
+		Rejected(ParaId, AssetId, AccountId, AssetBalance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The asset of the sibling parachain is already registered.
+		AlreadyRegistered,
+		/// The local asset already represents another foreign asset.
+		LocalAssetInUse,
+		/// The local asset was never issued or allocated.
+		UnknownLocalAsset,
+		/// The asset of the sibling parachain is not registered.
+		NotRegistered,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Register the asset `remote_id` of `para_id` as `local_id`, or as a new local asset if
+		/// `None`.
+		///
+		/// An explicit `local_id` must be an existing asset, so that no asset issued later gets
+		/// the same id.
+		#[weight = 20_000_000 + T::DbWeight::get().reads_writes(4, 3)]
+		pub fn register(
+			origin,
+			para_id: ParaId,
+			remote_id: AssetIdOf<T>,
+			local_id: Option<AssetIdOf<T>>,
+		) {
+			T::RegisterOrigin::ensure_origin(origin)?;
+			ensure!(
+				!<LocalAssetIds<T>>::contains_key(para_id, remote_id),
+				Error::<T>::AlreadyRegistered,
+			);
+
+			let local_id = match local_id {
+				Some(local_id) => {
+					ensure!(
+						T::AssetIdAllocator::is_allocated(local_id),
+						Error::<T>::UnknownLocalAsset,
+					);
+					ensure!(
+						!<ForeignAssets<T>>::contains_key(local_id),
+						Error::<T>::LocalAssetInUse,
+					);
+					local_id
+				}
+				None => T::AssetIdAllocator::allocate(),
+			};
+
+			Self::do_register(para_id, remote_id, local_id);
+		}
+
+		/// Remove the registration of the asset `remote_id` of `para_id`.
+		///
+		/// Balances of the local asset are kept, further transfers of the asset are handled
+		/// according to the policy.
+		#[weight = 20_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+		pub fn deregister(origin, para_id: ParaId, remote_id: AssetIdOf<T>) {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			let local_id = <LocalAssetIds<T>>::take(para_id, remote_id)
+				.ok_or(Error::<T>::NotRegistered)?;
+			<ForeignAssets<T>>::remove(local_id);
+
+			Self::deposit_event(RawEvent::Deregistered(para_id, remote_id));
+		}
+
+		/// Set what to do with assets of sibling parachains that are not registered.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_policy(origin, policy: RegistrationPolicy) {
+			T::RegisterOrigin::ensure_origin(origin)?;
+
+			Policy::put(policy);
+			Self::deposit_event(RawEvent::PolicySet(policy));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn do_register(para_id: ParaId, remote_id: AssetIdOf<T>, local_id: AssetIdOf<T>) {
+		<LocalAssetIds<T>>::insert(para_id, remote_id, local_id);
+		<ForeignAssets<T>>::insert(local_id, (para_id, remote_id));
+
+		Self::deposit_event(RawEvent::Registered(para_id, remote_id, local_id));
+	}
+
+	/// The local asset of the asset `remote_id` of `para_id`. Unknown assets are registered if
+	/// the policy allows it.
+	pub fn resolve(para_id: ParaId, remote_id: AssetIdOf<T>) -> Option<AssetIdOf<T>> {
+		Self::local_asset_id(para_id, remote_id).or_else(|| match Self::policy() {
+			RegistrationPolicy::AutoCreate => {
+				let local_id = T::AssetIdAllocator::allocate();
+				Self::do_register(para_id, remote_id, local_id);
+				Some(local_id)
+			}
+			RegistrationPolicy::Reject => None,
+		})
+	}
+
+	/// Mint `amount` of the asset `remote_id` received from `para_id` into the account `dest`.
+	/// Returns the local asset it was minted as.
+	pub fn receive(
+		para_id: ParaId,
+		remote_id: AssetIdOf<T>,
+		dest: T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> Result<AssetIdOf<T>, DispatchError> {
+		let local_id = match Self::resolve(para_id, remote_id) {
+			Some(local_id) => local_id,
+			None => {
+				Self::deposit_event(RawEvent::Rejected(para_id, remote_id, dest, amount));
+				return Err(Error::<T>::NotRegistered.into());
+			}
+		};
+
+		T::Assets::deposit(local_id, &dest, amount)?;
+		Self::deposit_event(RawEvent::Received(para_id, local_id, dest, amount));
+
+		Ok(local_id)
+	}
+}
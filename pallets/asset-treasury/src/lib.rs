@@ -0,0 +1,230 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that spends the `pallet_assets` assets held by the treasury.
+//!
+//! The native token of the treasury is managed by `pallet_treasury`, this pallet manages the
+//! assets held by the same account. Anyone may propose to spend an amount of an asset by
+//! reserving `ProposalBond`. Approved proposals are paid out right away, the bond of rejected
+//! ones is slashed. The pallet also collects the asset dust of the runtime: balances below the
+//! dust threshold `ApproveOrigin` set for their asset. Assets without a threshold have no dust.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	traits::{Currency, EnsureOrigin, Get, OnUnbalanced, ReservableCurrency},
+};
+use frame_system::ensure_signed;
+use parachain_primitives::{MultiAsset, OnAssetDust};
+use sp_runtime::{traits::Zero, RuntimeDebug};
+
+type AssetIdOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<
+	<T as frame_system::Trait>::AccountId,
+>>::NegativeImbalance;
+
+#[cfg(test)]
+mod tests;
+
+/// An index of a proposal.
+pub type ProposalIndex = u32;
+
+/// A proposal to spend an amount of an asset held by the treasury.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetProposal<AccountId, AssetId, AssetBalance, Balance> {
+	/// The account that made the proposal.
+	pub proposer: AccountId,
+	/// The asset to pay out.
+	pub asset_id: AssetId,
+	/// The amount of the asset to pay out.
+	pub value: AssetBalance,
+	/// The account receiving the payout.
+	pub beneficiary: AccountId,
+	/// The amount reserved from the proposer.
+	pub bond: Balance,
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The assets held by the treasury.
+	type Assets: MultiAsset<Self::AccountId>;
+
+	/// The currency proposal bonds are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// Origin from which approvals must come.
+	type ApproveOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Origin from which rejections must come.
+	type RejectOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Amount reserved from the proposer of a spend.
+	type ProposalBond: Get<BalanceOf<Self>>;
+
+	/// Handler for the bonds slashed from rejected proposals.
+	type OnSlash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The account of the treasury.
+	type TreasuryAccount: Get<Self::AccountId>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetTreasury {
+		/// Number of proposals that have been made.
+		ProposalCount get(fn proposal_count): ProposalIndex;
+
+		/// Proposals that have been made and are not yet approved or rejected.
+		Proposals get(fn proposals): map hasher(twox_64_concat) ProposalIndex
+			=> Option<AssetProposal<T::AccountId, AssetIdOf<T>, AssetBalanceOf<T>, BalanceOf<T>>>;
+
+		/// Balances of an asset below its threshold are dust. Zero if the asset has no dust.
+		DustThresholds get(fn dust_threshold):
+			map hasher(twox_64_concat) AssetIdOf<T> => AssetBalanceOf<T>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+		AssetBalance = AssetBalanceOf<T>,
+		Balance = BalanceOf<T>,
+	{
+		/// A new spend proposal was made.
+		Proposed(ProposalIndex),
+		/// A proposal was approved and the asset paid out to the beneficiary.
+		Awarded(ProposalIndex, AssetId, AssetBalance, AccountId),
+		/// A proposal was rejected and the bond slashed.
+		Rejected(ProposalIndex, Balance),
+		/// The treasury received asset dust.
+		DustReceived(AssetId, AssetBalance),
+		/// The dust threshold of an asset was changed.
+		DustThresholdSet(AssetId, AssetBalance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// No proposal at that index.
+		InvalidProposalIndex,
+		/// The treasury does not hold enough of the asset.
+		InsufficientTreasuryBalance,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Amount reserved from the proposer of a spend.
+		const ProposalBond: BalanceOf<T> = T::ProposalBond::get();
+
+		/// Propose to pay `value` of the asset to `beneficiary`, reserving `ProposalBond`.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+		pub fn propose_spend(
+			origin,
+			asset_id: AssetIdOf<T>,
+			value: AssetBalanceOf<T>,
+			beneficiary: T::AccountId,
+		) {
+			let proposer = ensure_signed(origin)?;
+
+			let bond = T::ProposalBond::get();
+			T::Currency::reserve(&proposer, bond)?;
+
+			let index = Self::proposal_count();
+			ProposalCount::put(index + 1);
+			<Proposals<T>>::insert(
+				index,
+				AssetProposal { proposer, asset_id, value, beneficiary, bond },
+			);
+
+			Self::deposit_event(RawEvent::Proposed(index));
+		}
+
+		/// Reject a proposal and slash its bond.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn reject_proposal(origin, #[compact] proposal_id: ProposalIndex) {
+			T::RejectOrigin::ensure_origin(origin)?;
+
+			let proposal = <Proposals<T>>::take(proposal_id)
+				.ok_or(Error::<T>::InvalidProposalIndex)?;
+			let imbalance = T::Currency::slash_reserved(&proposal.proposer, proposal.bond).0;
+			T::OnSlash::on_unbalanced(imbalance);
+
+			Self::deposit_event(RawEvent::Rejected(proposal_id, proposal.bond));
+		}
+
+		/// Approve a proposal, paying out the asset and returning the bond.
+		#[weight = 80_000_000 + T::DbWeight::get().reads_writes(4, 4)]
+		pub fn approve_proposal(origin, #[compact] proposal_id: ProposalIndex) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			let proposal = Self::proposals(proposal_id).ok_or(Error::<T>::InvalidProposalIndex)?;
+			T::Assets::transfer(
+				proposal.asset_id,
+				&T::TreasuryAccount::get(),
+				&proposal.beneficiary,
+				proposal.value,
+			)
+			.map_err(|_| Error::<T>::InsufficientTreasuryBalance)?;
+
+			<Proposals<T>>::remove(proposal_id);
+			T::Currency::unreserve(&proposal.proposer, proposal.bond);
+
+			Self::deposit_event(RawEvent::Awarded(
+				proposal_id,
+				proposal.asset_id,
+				proposal.value,
+				proposal.beneficiary,
+			));
+		}
+
+		/// Set the balance below which an asset is dust. Zero means it has no dust.
+		///
+		/// The threshold depends on the decimals and the value of the asset, so it is set per
+		/// asset.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_dust_threshold(
+			origin,
+			asset_id: AssetIdOf<T>,
+			threshold: AssetBalanceOf<T>,
+		) {
+			T::ApproveOrigin::ensure_origin(origin)?;
+
+			if threshold.is_zero() {
+				<DustThresholds<T>>::remove(asset_id);
+			} else {
+				<DustThresholds<T>>::insert(asset_id, threshold);
+			}
+			Self::deposit_event(RawEvent::DustThresholdSet(asset_id, threshold));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether `balance` of the asset is dust.
+	pub fn is_dust(asset_id: AssetIdOf<T>, balance: AssetBalanceOf<T>) -> bool {
+		!balance.is_zero() && balance < Self::dust_threshold(asset_id)
+	}
+}
+
+impl<T: Trait> OnAssetDust<AssetIdOf<T>, AssetBalanceOf<T>> for Module<T> {
+	fn on_dust(asset_id: AssetIdOf<T>, amount: AssetBalanceOf<T>) {
+		if T::Assets::deposit(asset_id, &T::TreasuryAccount::get(), amount).is_ok() {
+			Self::deposit_event(RawEvent::DustReceived(asset_id, amount));
+		}
+	}
+}
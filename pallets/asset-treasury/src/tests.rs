@@ -0,0 +1,293 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the spend proposals and the asset dust.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, dispatch::DispatchResult, impl_outer_event, impl_outer_origin,
+	parameter_types, weights::Weight, StorageMap,
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+use std::{cell::RefCell, collections::HashMap};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod asset_treasury {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		asset_treasury<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static ASSETS: RefCell<HashMap<(u32, u64), u64>> = RefCell::new(HashMap::new());
+}
+
+/// Assets kept in a map, without total supply.
+pub struct TestAssets;
+
+impl MultiAsset<u64> for TestAssets {
+	type AssetId = u32;
+	type Balance = u64;
+
+	fn balance(asset_id: u32, who: &u64) -> u64 {
+		ASSETS.with(|a| a.borrow().get(&(asset_id, *who)).copied().unwrap_or_default())
+	}
+
+	fn total_supply(_asset_id: u32) -> u64 {
+		0
+	}
+
+	fn transfer(asset_id: u32, from: &u64, to: &u64, amount: u64) -> DispatchResult {
+		Self::withdraw(asset_id, from, amount)?;
+		Self::deposit(asset_id, to, amount)
+	}
+
+	fn deposit(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who) + amount;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+
+	fn withdraw(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who)
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Insufficient asset balance"))?;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+}
+
+parameter_types! {
+	pub const ProposalBond: u64 = 10;
+	pub const TreasuryAccount: u64 = TREASURY;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Assets = TestAssets;
+	type Currency = Balances;
+	type ApproveOrigin = frame_system::EnsureRoot<u64>;
+	type RejectOrigin = frame_system::EnsureRoot<u64>;
+	type ProposalBond = ProposalBond;
+	type OnSlash = ();
+	type TreasuryAccount = TreasuryAccount;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type AssetTreasury = Module<Test>;
+
+const ALICE: u64 = 1;
+const BOB: u64 = 2;
+const TREASURY: u64 = 99;
+const ASSET: u32 = 7;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 100)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		TestAssets::deposit(ASSET, &TREASURY, 1_000).unwrap();
+	});
+	ext
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn propose_spend_reserves_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_spend(Origin::signed(ALICE), ASSET, 300, BOB));
+
+		assert_eq!(Balances::reserved_balance(ALICE), 10);
+		assert_eq!(AssetTreasury::proposal_count(), 1);
+		assert_eq!(
+			AssetTreasury::proposals(0),
+			Some(AssetProposal {
+				proposer: ALICE,
+				asset_id: ASSET,
+				value: 300,
+				beneficiary: BOB,
+				bond: 10,
+			}),
+		);
+		assert_eq!(last_event(), TestEvent::asset_treasury(RawEvent::Proposed(0)));
+	});
+}
+
+#[test]
+fn propose_spend_requires_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetTreasury::propose_spend(Origin::signed(BOB), ASSET, 300, BOB),
+			pallet_balances::Error::<Test, pallet_balances::DefaultInstance>::InsufficientBalance,
+		);
+	});
+}
+
+#[test]
+fn approve_proposal_pays_out_and_returns_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_spend(Origin::signed(ALICE), ASSET, 300, BOB));
+		assert_noop!(AssetTreasury::approve_proposal(Origin::signed(ALICE), 0), BadOrigin);
+
+		assert_ok!(AssetTreasury::approve_proposal(RawOrigin::Root.into(), 0));
+
+		assert_eq!(TestAssets::balance(ASSET, &BOB), 300);
+		assert_eq!(TestAssets::balance(ASSET, &TREASURY), 700);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 100);
+		assert_eq!(AssetTreasury::proposals(0), None);
+		assert_eq!(
+			last_event(),
+			TestEvent::asset_treasury(RawEvent::Awarded(0, ASSET, 300, BOB)),
+		);
+	});
+}
+
+#[test]
+fn approve_proposal_keeps_it_when_the_treasury_is_short() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_spend(Origin::signed(ALICE), ASSET, 2_000, BOB));
+
+		assert_noop!(
+			AssetTreasury::approve_proposal(RawOrigin::Root.into(), 0),
+			Error::<Test>::InsufficientTreasuryBalance,
+		);
+		assert_noop!(
+			AssetTreasury::approve_proposal(RawOrigin::Root.into(), 1),
+			Error::<Test>::InvalidProposalIndex,
+		);
+	});
+}
+
+#[test]
+fn reject_proposal_slashes_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::propose_spend(Origin::signed(ALICE), ASSET, 300, BOB));
+		assert_noop!(AssetTreasury::reject_proposal(Origin::signed(ALICE), 0), BadOrigin);
+
+		assert_ok!(AssetTreasury::reject_proposal(RawOrigin::Root.into(), 0));
+
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 90);
+		assert_eq!(Balances::total_issuance(), 90);
+		assert_eq!(TestAssets::balance(ASSET, &TREASURY), 1_000);
+		assert_eq!(AssetTreasury::proposals(0), None);
+		assert_eq!(last_event(), TestEvent::asset_treasury(RawEvent::Rejected(0, 10)));
+	});
+}
+
+#[test]
+fn assets_have_no_dust_until_a_threshold_is_set() {
+	new_test_ext().execute_with(|| {
+		assert!(!AssetTreasury::is_dust(ASSET, 1));
+
+		assert_noop!(
+			AssetTreasury::set_dust_threshold(Origin::signed(ALICE), ASSET, 50),
+			BadOrigin,
+		);
+		assert_ok!(AssetTreasury::set_dust_threshold(RawOrigin::Root.into(), ASSET, 50));
+
+		assert!(!AssetTreasury::is_dust(ASSET, 0));
+		assert!(AssetTreasury::is_dust(ASSET, 49));
+		assert!(!AssetTreasury::is_dust(ASSET, 50));
+		assert!(!AssetTreasury::is_dust(ASSET + 1, 49));
+		assert_eq!(
+			last_event(),
+			TestEvent::asset_treasury(RawEvent::DustThresholdSet(ASSET, 50)),
+		);
+	});
+}
+
+#[test]
+fn zero_threshold_removes_the_dust() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(AssetTreasury::set_dust_threshold(RawOrigin::Root.into(), ASSET, 50));
+		assert_ok!(AssetTreasury::set_dust_threshold(RawOrigin::Root.into(), ASSET, 0));
+
+		assert!(!AssetTreasury::is_dust(ASSET, 49));
+		assert!(!DustThresholds::<Test>::contains_key(ASSET));
+	});
+}
+
+#[test]
+fn dust_goes_to_the_treasury() {
+	new_test_ext().execute_with(|| {
+		AssetTreasury::on_dust(ASSET, 20);
+
+		assert_eq!(TestAssets::balance(ASSET, &TREASURY), 1_020);
+		assert_eq!(last_event(), TestEvent::asset_treasury(RawEvent::DustReceived(ASSET, 20)));
+	});
+}
@@ -0,0 +1,303 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that lets signers pay transaction fees in `pallet_assets` assets.
+//!
+//! `ChargeAssetTxPayment` replaces `ChargeTransactionPayment` in the signed extra of the runtime.
+//! Without an asset id it charges the native currency exactly like `ChargeTransactionPayment`.
+//! With an asset id the native fee is converted at the rate set for the asset by `UpdateOrigin`
+//! and the asset is held in the pot account of this pallet. After dispatch the fee for unused
+//! weight is refunded in the same asset, and `OnAssetFee` pays out the rest of the fee and the
+//! tip from the pot.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchResult,
+	ensure,
+	traits::{Currency, EnsureOrigin, Get},
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use parachain_primitives::MultiAsset;
+use sp_runtime::{
+	traits::{
+		AccountIdConversion, DispatchInfoOf, Dispatchable, PostDispatchInfoOf,
+		SaturatedConversion, Saturating, SignedExtension, Zero,
+	},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError,
+		ValidTransaction,
+	},
+	FixedPointNumber, FixedU128, ModuleId,
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> = <<T as pallet_transaction_payment::Trait>::Currency as Currency<
+	<T as frame_system::Trait>::AccountId,
+>>::Balance;
+type AssetIdOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<<T as Trait>::Assets as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Pays out the fees paid in assets.
+pub trait OnAssetFee<AccountId, AssetId, Balance> {
+	/// Pay out `fee` and `tip` of the asset, both held by `pot`.
+	fn on_asset_fee(pot: &AccountId, asset_id: AssetId, fee: Balance, tip: Balance);
+}
+
+/// Leaves the fees in the pot.
+impl<AccountId, AssetId, Balance> OnAssetFee<AccountId, AssetId, Balance> for () {
+	fn on_asset_fee(_pot: &AccountId, _asset_id: AssetId, _fee: Balance, _tip: Balance) {}
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: pallet_transaction_payment::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The assets fees can be paid in.
+	type Assets: MultiAsset<Self::AccountId>;
+
+	/// Origin that may set the conversion rates.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Pays out the fees and tips paid in assets.
+	type OnAssetFee: OnAssetFee<Self::AccountId, AssetIdOf<Self>, AssetBalanceOf<Self>>;
+
+	/// Identifier of the pot account that holds fees paid in assets during dispatch.
+	type ModuleId: Get<ModuleId>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AssetTxPayment {
+		/// Amount of an asset charged for one unit of the native fee. Fees can only be paid in
+		/// assets that have a rate.
+		ConversionRates get(fn conversion_rate):
+			map hasher(twox_64_concat) AssetIdOf<T> => Option<FixedU128>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+		AssetBalance = AssetBalanceOf<T>,
+	{
+		/// The conversion rate of an asset was set, or removed if `None`.
+		ConversionRateSet(AssetId, Option<FixedU128>),
+		/// An account paid the fee of a transaction in an asset, after refunds.
+		AssetFeePaid(AccountId, AssetId, AssetBalance),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// A conversion rate of zero would make fees free.
+		ZeroRate,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Identifier of the pot account that holds fees paid in assets during dispatch.
+		const ModuleId: ModuleId = T::ModuleId::get();
+
+		/// Set the amount of `asset_id` charged per unit of the native fee, or disable paying
+		/// fees in the asset if `None`.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_conversion_rate(origin, asset_id: AssetIdOf<T>, rate: Option<FixedU128>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(rate.map_or(true, |rate| !rate.is_zero()), Error::<T>::ZeroRate);
+
+			match rate {
+				Some(rate) => <ConversionRates<T>>::insert(asset_id, rate),
+				None => <ConversionRates<T>>::remove(asset_id),
+			}
+			Self::deposit_event(RawEvent::ConversionRateSet(asset_id, rate));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account of the pot.
+	pub fn account_id() -> T::AccountId {
+		T::ModuleId::get().into_account()
+	}
+
+	/// Convert a fee in the native currency to the given asset.
+	pub fn to_asset_fee(asset_id: AssetIdOf<T>, fee: BalanceOf<T>) -> Option<AssetBalanceOf<T>> {
+		let rate = Self::conversion_rate(asset_id)?;
+		let fee: u128 = fee.saturated_into();
+
+		Some(rate.saturating_mul_int(fee).saturated_into())
+	}
+}
+
+/// The fee payment of a transaction, as passed from `pre_dispatch` to `post_dispatch`.
+pub enum Payment<T: Trait + Send + Sync>
+where
+	BalanceOf<T>: Send + Sync,
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	/// Paid in the native currency by `ChargeTransactionPayment`.
+	Native(<ChargeTransactionPayment<T> as SignedExtension>::Pre),
+	/// Paid in an asset: the tip, the signer, the asset and the amount paid.
+	Asset(BalanceOf<T>, T::AccountId, AssetIdOf<T>, AssetBalanceOf<T>),
+}
+
+/// Require the transactor to pay for themselves, either in the native currency or in the given
+/// asset. Also adds a tip, expressed in the native currency.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeAssetTxPayment<T: Trait + Send + Sync> {
+	#[codec(compact)]
+	tip: BalanceOf<T>,
+	asset_id: Option<AssetIdOf<T>>,
+}
+
+impl<T: Trait + Send + Sync> ChargeAssetTxPayment<T>
+where
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	/// Utility constructor.
+	pub fn from(tip: BalanceOf<T>, asset_id: Option<AssetIdOf<T>>) -> Self {
+		Self { tip, asset_id }
+	}
+
+	fn asset_fee(
+		&self,
+		asset_id: AssetIdOf<T>,
+		info: &DispatchInfoOf<T::Call>,
+		len: usize,
+	) -> Result<(BalanceOf<T>, AssetBalanceOf<T>), TransactionValidityError> {
+		let fee = pallet_transaction_payment::Module::<T>::compute_fee(len as u32, info, self.tip);
+		let asset_fee = Module::<T>::to_asset_fee(asset_id, fee)
+			.ok_or(InvalidTransaction::Payment)?;
+
+		Ok((fee, asset_fee))
+	}
+}
+
+impl<T: Trait + Send + Sync> sp_std::fmt::Debug for ChargeAssetTxPayment<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeAssetTxPayment<{:?}, {:?}>", self.tip, self.asset_id)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + Send + Sync> SignedExtension for ChargeAssetTxPayment<T>
+where
+	BalanceOf<T>: Send + Sync,
+	AssetIdOf<T>: Send + Sync,
+	T::Call: Dispatchable<Info = DispatchInfo, PostInfo = PostDispatchInfo>,
+{
+	const IDENTIFIER: &'static str = "ChargeAssetTxPayment";
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type Pre = Option<Payment<T>>;
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		match self.asset_id {
+			None => ChargeTransactionPayment::<T>::from(self.tip).validate(who, call, info, len),
+			Some(asset_id) => {
+				let (fee, asset_fee) = self.asset_fee(asset_id, info, len)?;
+				if T::Assets::balance(asset_id, who) < asset_fee {
+					return Err(InvalidTransaction::Payment.into());
+				}
+
+				Ok(ValidTransaction {
+					priority: fee.saturated_into::<TransactionPriority>(),
+					..Default::default()
+				})
+			}
+		}
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		match self.asset_id {
+			None => ChargeTransactionPayment::<T>::from(self.tip)
+				.pre_dispatch(who, call, info, len)
+				.map(|pre| Some(Payment::Native(pre))),
+			Some(asset_id) => {
+				let (_, asset_fee) = self.asset_fee(asset_id, info, len)?;
+				if !asset_fee.is_zero() {
+					T::Assets::transfer(asset_id, who, &Module::<T>::account_id(), asset_fee)
+						.map_err(|_| InvalidTransaction::Payment)?;
+				}
+
+				Ok(Some(Payment::Asset(self.tip, who.clone(), asset_id, asset_fee)))
+			}
+		}
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(Payment::Native(pre)) => {
+				ChargeTransactionPayment::<T>::post_dispatch(pre, info, post_info, len, result)
+			}
+			Some(Payment::Asset(tip, who, asset_id, paid)) => {
+				let actual_fee = pallet_transaction_payment::Module::<T>::compute_actual_fee(
+					len as u32, info, post_info, tip,
+				);
+				let actual_asset_fee =
+					Module::<T>::to_asset_fee(asset_id, actual_fee).unwrap_or(paid);
+				let refund = paid.saturating_sub(actual_asset_fee);
+				let pot = Module::<T>::account_id();
+
+				let paid = if !refund.is_zero()
+					&& T::Assets::transfer(asset_id, &pot, &who, refund).is_ok()
+				{
+					paid - refund
+				} else {
+					paid
+				};
+
+				let asset_tip = Module::<T>::to_asset_fee(asset_id, tip)
+					.unwrap_or_else(Zero::zero)
+					.min(paid);
+				T::OnAssetFee::on_asset_fee(&pot, asset_id, paid - asset_tip, asset_tip);
+
+				Module::<T>::deposit_event(RawEvent::AssetFeePaid(who, asset_id, paid));
+				Ok(())
+			}
+			None => Ok(()),
+		}
+	}
+}
@@ -0,0 +1,311 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of paying transaction fees in assets.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+	weights::{IdentityFee, Weight},
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+use std::{cell::RefCell, collections::HashMap};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod asset_tx_payment {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		asset_tx_payment<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = Call<Test>;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const TransactionByteFee: u64 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl pallet_transaction_payment::Trait for Test {
+	type Currency = pallet_balances::Module<Test>;
+	type OnTransactionPayment = ();
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+
+thread_local! {
+	static BALANCES: RefCell<HashMap<(u32, u64), u64>> = RefCell::new(HashMap::new());
+	static PAID_OUT: RefCell<Vec<(u32, u64, u64)>> = RefCell::new(Vec::new());
+}
+
+/// Assets kept in a map, without total supply.
+pub struct TestAssets;
+
+impl MultiAsset<u64> for TestAssets {
+	type AssetId = u32;
+	type Balance = u64;
+
+	fn balance(asset_id: u32, who: &u64) -> u64 {
+		BALANCES.with(|b| b.borrow().get(&(asset_id, *who)).copied().unwrap_or_default())
+	}
+
+	fn total_supply(_asset_id: u32) -> u64 {
+		0
+	}
+
+	fn transfer(asset_id: u32, from: &u64, to: &u64, amount: u64) -> DispatchResult {
+		Self::withdraw(asset_id, from, amount)?;
+		Self::deposit(asset_id, to, amount)
+	}
+
+	fn deposit(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who) + amount;
+		BALANCES.with(|b| b.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+
+	fn withdraw(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who)
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Insufficient asset balance"))?;
+		BALANCES.with(|b| b.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+}
+
+const COLLECTOR: u64 = 100;
+
+/// Moves the fees to `COLLECTOR` and records them.
+pub struct CollectFees;
+
+impl OnAssetFee<u64, u32, u64> for CollectFees {
+	fn on_asset_fee(pot: &u64, asset_id: u32, fee: u64, tip: u64) {
+		TestAssets::transfer(asset_id, pot, &COLLECTOR, fee + tip).unwrap();
+		PAID_OUT.with(|paid| paid.borrow_mut().push((asset_id, fee, tip)));
+	}
+}
+
+parameter_types! {
+	pub const AssetTxPaymentModuleId: ModuleId = ModuleId(*b"py/astxp");
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Assets = TestAssets;
+	type UpdateOrigin = frame_system::EnsureRoot<u64>;
+	type OnAssetFee = CollectFees;
+	type ModuleId = AssetTxPaymentModuleId;
+}
+
+type System = frame_system::Module<Test>;
+type AssetTxPayment = Module<Test>;
+
+const ALICE: u64 = 1;
+const ASSET: u32 = 7;
+const CALL: &Call<Test> = &Call::set_conversion_rate(ASSET, None);
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	BALANCES.with(|b| b.borrow_mut().clear());
+	PAID_OUT.with(|paid| paid.borrow_mut().clear());
+
+	let storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		TestAssets::deposit(ASSET, &ALICE, 1_000).unwrap();
+	});
+	ext
+}
+
+fn info(weight: Weight) -> DispatchInfo {
+	DispatchInfo { weight, ..Default::default() }
+}
+
+fn paid_out() -> Vec<(u32, u64, u64)> {
+	PAID_OUT.with(|paid| paid.borrow().clone())
+}
+
+fn set_rate(rate: u128) {
+	assert_ok!(AssetTxPayment::set_conversion_rate(
+		RawOrigin::Root.into(),
+		ASSET,
+		Some(FixedU128::saturating_from_integer(rate)),
+	));
+}
+
+#[test]
+fn fee_is_charged_in_the_asset_at_the_rate() {
+	new_test_ext().execute_with(|| {
+		set_rate(2);
+
+		// Weight 100 and length 10 cost a native fee of 110.
+		let pre = ChargeAssetTxPayment::<Test>::from(0, Some(ASSET))
+			.pre_dispatch(&ALICE, CALL, &info(100), 10)
+			.unwrap();
+		assert_eq!(TestAssets::balance(ASSET, &ALICE), 780);
+		assert_eq!(TestAssets::balance(ASSET, &AssetTxPayment::account_id()), 220);
+
+		assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+			pre,
+			&info(100),
+			&Default::default(),
+			10,
+			&Ok(()),
+		));
+		assert_eq!(TestAssets::balance(ASSET, &COLLECTOR), 220);
+		assert_eq!(TestAssets::balance(ASSET, &AssetTxPayment::account_id()), 0);
+		assert_eq!(paid_out(), vec![(ASSET, 220, 0)]);
+	});
+}
+
+#[test]
+fn unused_weight_is_refunded_in_the_asset() {
+	new_test_ext().execute_with(|| {
+		set_rate(2);
+
+		let pre = ChargeAssetTxPayment::<Test>::from(0, Some(ASSET))
+			.pre_dispatch(&ALICE, CALL, &info(100), 10)
+			.unwrap();
+		let post_info: PostDispatchInfo = Some(50).into();
+		assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+			pre,
+			&info(100),
+			&post_info,
+			10,
+			&Ok(()),
+		));
+
+		// Only the native fee of 60 for the used weight is paid.
+		assert_eq!(TestAssets::balance(ASSET, &ALICE), 880);
+		assert_eq!(TestAssets::balance(ASSET, &COLLECTOR), 120);
+		assert_eq!(paid_out(), vec![(ASSET, 120, 0)]);
+	});
+}
+
+#[test]
+fn tip_is_paid_out_separately() {
+	new_test_ext().execute_with(|| {
+		set_rate(2);
+
+		let pre = ChargeAssetTxPayment::<Test>::from(5, Some(ASSET))
+			.pre_dispatch(&ALICE, CALL, &info(100), 10)
+			.unwrap();
+		assert_ok!(ChargeAssetTxPayment::<Test>::post_dispatch(
+			pre,
+			&info(100),
+			&Default::default(),
+			10,
+			&Ok(()),
+		));
+
+		assert_eq!(TestAssets::balance(ASSET, &ALICE), 770);
+		assert_eq!(paid_out(), vec![(ASSET, 220, 10)]);
+	});
+}
+
+#[test]
+fn asset_without_rate_cannot_pay() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			ChargeAssetTxPayment::<Test>::from(0, Some(ASSET))
+				.pre_dispatch(&ALICE, CALL, &info(100), 10)
+				.err(),
+			Some(InvalidTransaction::Payment.into()),
+		);
+		assert_eq!(TestAssets::balance(ASSET, &ALICE), 1_000);
+	});
+}
+
+#[test]
+fn insufficient_asset_balance_is_invalid() {
+	new_test_ext().execute_with(|| {
+		set_rate(10);
+
+		// The fee of 1_100 is above the balance of 1_000.
+		assert_eq!(
+			ChargeAssetTxPayment::<Test>::from(0, Some(ASSET))
+				.validate(&ALICE, CALL, &info(100), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+		assert!(ChargeAssetTxPayment::<Test>::from(0, Some(ASSET))
+			.pre_dispatch(&ALICE, CALL, &info(100), 10)
+			.is_err());
+	});
+}
+
+#[test]
+fn zero_rate_is_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			AssetTxPayment::set_conversion_rate(
+				RawOrigin::Root.into(),
+				ASSET,
+				Some(FixedU128::zero()),
+			),
+			Error::<Test>::ZeroRate,
+		);
+
+		assert_ok!(AssetTxPayment::set_conversion_rate(RawOrigin::Root.into(), ASSET, None));
+	});
+}
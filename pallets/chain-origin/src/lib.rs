@@ -0,0 +1,171 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that provides the origins of the relay chain and of sibling parachains.
+//!
+//! Calls sent by the relay chain in `ParachainSpecific` downward messages are dispatched with the
+//! `Relay` origin, unless they weigh more than `MaxRelayCallWeight`, and calls sent by sibling
+//! parachains carry their `SiblingParachain` origin.
+//! `EnsureRelay` and `EnsureSiblingParachain` let pallets accept these origins, and
+//! `RootDispatchOrigin` may dispatch calls as root, e.g. to let relay-chain governance upgrade
+//! the runtime. Relay calls of `dispatch_as_root` are not capped by `MaxRelayCallWeight`, as a
+//! runtime upgrade weighs more than any other call.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, DecodeLimit, Encode};
+use cumulus_primitives::{DownwardMessage, DownwardMessageHandler, ParaId};
+use frame_support::{
+	decl_event, decl_module,
+	dispatch::{DispatchResult, Dispatchable, Parameter, PostDispatchInfo},
+	traits::{EnsureOrigin, Get, IsSubType},
+	weights::{GetDispatchInfo, Weight},
+};
+use sp_runtime::RuntimeDebug;
+use sp_std::{marker::PhantomData, prelude::*};
+
+#[cfg(test)]
+mod tests;
+
+/// The deepest nesting of calls decoded from a downward message.
+const MAX_CALL_DEPTH: u32 = 64;
+
+/// Origin of the calls sent by other chains.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum Origin {
+	/// The call was sent by the relay chain.
+	Relay,
+	/// The call was sent by this sibling parachain.
+	SiblingParachain(ParaId),
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The overarching origin type.
+	type Origin: From<Origin> + From<frame_system::RawOrigin<Self::AccountId>>;
+
+	/// The calls that other chains can send.
+	type Call: Parameter
+		+ Dispatchable<Origin = <Self as Trait>::Origin, PostInfo = PostDispatchInfo>
+		+ GetDispatchInfo
+		+ IsSubType<Call<Self>>;
+
+	/// Origin that may dispatch calls as root.
+	type RootDispatchOrigin: EnsureOrigin<<Self as frame_system::Trait>::Origin>;
+
+	/// The most weight a call of the relay chain may use, unless it is `dispatch_as_root`.
+	type MaxRelayCallWeight: Get<Weight>;
+}
+
+decl_event!(
+	pub enum Event {
+		/// A call of the relay chain was dispatched.
+		RelayDispatched(DispatchResult),
+		/// A downward message did not contain a call.
+		RelayCallUndecodable,
+		/// A call of the relay chain was not dispatched because of its weight.
+		RelayCallOverweight(Weight),
+		/// A call was dispatched as root.
+		DispatchedAsRoot(DispatchResult),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call
+		where origin: <T as frame_system::Trait>::Origin
+	{
+		fn deposit_event() = default;
+
+		/// The most weight a call of the relay chain may use, unless it is `dispatch_as_root`.
+		const MaxRelayCallWeight: Weight = T::MaxRelayCallWeight::get();
+
+		/// Dispatch `call` with the root origin.
+		#[weight = (
+			call.get_dispatch_info().weight.saturating_add(10_000_000),
+			call.get_dispatch_info().class,
+		)]
+		pub fn dispatch_as_root(origin, call: Box<<T as Trait>::Call>) {
+			T::RootDispatchOrigin::ensure_origin(origin)?;
+
+			let result = call
+				.dispatch(frame_system::RawOrigin::Root.into())
+				.map(|_| ())
+				.map_err(|e| e.error);
+			Self::deposit_event(Event::DispatchedAsRoot(result));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Dispatch a call sent by the relay chain, if it weighs at most `MaxRelayCallWeight` or
+	/// dispatches a call as root.
+	fn dispatch_relay(encoded_call: &[u8]) {
+		let decoded = <T as Trait>::Call::decode_all_with_depth_limit(MAX_CALL_DEPTH, encoded_call);
+		let call = match decoded {
+			Ok(call) => call,
+			Err(_) => {
+				Self::deposit_event(Event::RelayCallUndecodable);
+				return;
+			}
+		};
+
+		let weight = call.get_dispatch_info().weight;
+		let as_root = matches!(call.is_sub_type(), Some(Call::dispatch_as_root(..)));
+		if weight > T::MaxRelayCallWeight::get() && !as_root {
+			Self::deposit_event(Event::RelayCallOverweight(weight));
+			return;
+		}
+
+		let result = call.dispatch(Origin::Relay.into()).map(|_| ()).map_err(|e| e.error);
+		Self::deposit_event(Event::RelayDispatched(result));
+	}
+}
+
+/// Dispatches the calls in `ParachainSpecific` downward messages and ignores other messages.
+impl<T: Trait> DownwardMessageHandler for Module<T> {
+	fn handle_downward_message(msg: &DownwardMessage) {
+		if let DownwardMessage::ParachainSpecific(encoded_call) = msg {
+			Self::dispatch_relay(encoded_call);
+		}
+	}
+}
+
+/// Ensures a call was sent by the relay chain.
+pub struct EnsureRelay<O>(PhantomData<O>);
+
+impl<O: Into<Result<Origin, O>> + From<Origin>> EnsureOrigin<O> for EnsureRelay<O> {
+	type Success = ();
+
+	fn try_origin(o: O) -> Result<Self::Success, O> {
+		o.into().and_then(|o| match o {
+			Origin::Relay => Ok(()),
+			o => Err(O::from(o)),
+		})
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_origin() -> O {
+		O::from(Origin::Relay)
+	}
+}
+
+/// Ensures a call was sent by a sibling parachain, returning its id.
+pub struct EnsureSiblingParachain<O>(PhantomData<O>);
+
+impl<O: Into<Result<Origin, O>> + From<Origin>> EnsureOrigin<O> for EnsureSiblingParachain<O> {
+	type Success = ParaId;
+
+	fn try_origin(o: O) -> Result<Self::Success, O> {
+		o.into().and_then(|o| match o {
+			Origin::SiblingParachain(para_id) => Ok(para_id),
+			o => Err(O::from(o)),
+		})
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn successful_origin() -> O {
+		O::from(Origin::SiblingParachain(ParaId::from(0)))
+	}
+}
@@ -0,0 +1,201 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the origins of other chains and of the dispatch of relay-chain calls.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_dispatch, impl_outer_event, impl_outer_origin,
+	parameter_types,
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+
+mod chain_origin {
+	pub use crate::{Call, Event, Origin};
+}
+
+impl_outer_origin! {
+	pub enum TestOrigin for Test where system = frame_system {
+		chain_origin,
+	}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		chain_origin,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum OuterCall for Test where origin: TestOrigin {
+		frame_system::System,
+		chain_origin::ChainOrigin,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = TestOrigin;
+	type Call = OuterCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const MaxRelayCallWeight: Weight = 100;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Origin = TestOrigin;
+	type Call = OuterCall;
+	type RootDispatchOrigin = EnsureRelay<TestOrigin>;
+	type MaxRelayCallWeight = MaxRelayCallWeight;
+}
+
+type System = frame_system::Module<Test>;
+type ChainOrigin = Module<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// A call that only root may dispatch, weighing `percent` of a block.
+fn fill_block(percent: u32) -> OuterCall {
+	OuterCall::System(frame_system::Call::fill_block(Perbill::from_percent(percent)))
+}
+
+fn relay_sends(call: &OuterCall) {
+	ChainOrigin::handle_downward_message(&DownwardMessage::ParachainSpecific(call.encode()));
+}
+
+fn events() -> Vec<TestEvent> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn ensure_relay_accepts_only_the_relay() {
+	let relay = TestOrigin::from(Origin::Relay);
+	let sibling = TestOrigin::from(Origin::SiblingParachain(ParaId::from(200)));
+
+	assert_eq!(EnsureRelay::<TestOrigin>::ensure_origin(relay), Ok(()));
+	assert!(EnsureRelay::<TestOrigin>::ensure_origin(sibling).is_err());
+	assert!(EnsureRelay::<TestOrigin>::ensure_origin(RawOrigin::Root.into()).is_err());
+	assert!(EnsureRelay::<TestOrigin>::ensure_origin(TestOrigin::signed(1)).is_err());
+}
+
+#[test]
+fn ensure_sibling_parachain_returns_its_id() {
+	let relay = TestOrigin::from(Origin::Relay);
+	let sibling = TestOrigin::from(Origin::SiblingParachain(ParaId::from(200)));
+
+	type Ensure = EnsureSiblingParachain<TestOrigin>;
+
+	assert_eq!(Ensure::ensure_origin(sibling), Ok(ParaId::from(200)));
+	assert!(Ensure::ensure_origin(relay).is_err());
+	assert!(Ensure::ensure_origin(RawOrigin::Root.into()).is_err());
+	assert!(Ensure::ensure_origin(TestOrigin::signed(1)).is_err());
+}
+
+#[test]
+fn relay_calls_are_dispatched_with_the_relay_origin() {
+	new_test_ext().execute_with(|| {
+		relay_sends(&fill_block(5));
+
+		assert_eq!(
+			last_event(),
+			TestEvent::chain_origin(Event::RelayDispatched(Err(DispatchError::BadOrigin))),
+		);
+	});
+}
+
+#[test]
+fn relay_calls_above_the_weight_cap_are_not_dispatched() {
+	new_test_ext().execute_with(|| {
+		relay_sends(&fill_block(50));
+		assert_eq!(last_event(), TestEvent::chain_origin(Event::RelayCallOverweight(512)));
+
+		ChainOrigin::handle_downward_message(&DownwardMessage::ParachainSpecific(vec![255]));
+		assert_eq!(last_event(), TestEvent::chain_origin(Event::RelayCallUndecodable));
+	});
+}
+
+#[test]
+fn relay_calls_dispatching_as_root_are_not_capped() {
+	new_test_ext().execute_with(|| {
+		let as_root = OuterCall::ChainOrigin(Call::dispatch_as_root(Box::new(fill_block(50))));
+		assert!(as_root.get_dispatch_info().weight > MaxRelayCallWeight::get());
+
+		relay_sends(&as_root);
+
+		assert_eq!(
+			events(),
+			vec![
+				TestEvent::chain_origin(Event::DispatchedAsRoot(Ok(()))),
+				TestEvent::chain_origin(Event::RelayDispatched(Ok(()))),
+			],
+		);
+	});
+}
+
+#[test]
+fn only_the_root_dispatch_origin_dispatches_as_root() {
+	new_test_ext().execute_with(|| {
+		let sibling = TestOrigin::from(Origin::SiblingParachain(ParaId::from(200)));
+		let call = Box::new(fill_block(5));
+
+		assert_noop!(ChainOrigin::dispatch_as_root(sibling, call.clone()), BadOrigin);
+		assert_noop!(
+			ChainOrigin::dispatch_as_root(RawOrigin::Root.into(), call.clone()),
+			BadOrigin,
+		);
+
+		assert_ok!(ChainOrigin::dispatch_as_root(Origin::Relay.into(), call));
+		assert_eq!(last_event(), TestEvent::chain_origin(Event::DispatchedAsRoot(Ok(()))));
+	});
+}
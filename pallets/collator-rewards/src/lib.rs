@@ -0,0 +1,163 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that pays transaction fees and tips out to the author of the block.
+//!
+//! Used as `OnTransactionPayment` of the transaction payment pallet. A configurable share of the
+//! fees and of the tips of every transaction goes to the block author, the rest to
+//! `Beneficiary`. Without an author, everything goes to `Beneficiary`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use frame_support::{
+	decl_event, decl_module, decl_storage,
+	traits::{Currency, EnsureOrigin, FindAuthor, Get, Imbalance, OnUnbalanced},
+	weights::Weight,
+};
+use sp_runtime::{
+	traits::{Saturating, Zero},
+	Perbill,
+};
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Negative imbalance of the currency fees are paid in.
+pub type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<
+	<T as frame_system::Trait>::AccountId,
+>>::NegativeImbalance;
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency fees are paid in.
+	type Currency: Currency<Self::AccountId>;
+
+	/// Finds the author of the current block.
+	type FindAuthor: FindAuthor<Self::AccountId>;
+
+	/// Receives the part of fees and tips that does not go to the author.
+	type Beneficiary: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Origin that may change the shares of the author.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as CollatorRewards {
+		/// Share of the transaction fees paid to the block author.
+		FeeAuthorShare get(fn fee_author_share) config(): Perbill;
+
+		/// Share of the tips paid to the block author.
+		TipAuthorShare get(fn tip_author_share) config(): Perbill;
+
+		/// Total amount every collator received as block author.
+		AuthorRewards get(fn author_rewards):
+			map hasher(blake2_128_concat) T::AccountId => BalanceOf<T>;
+
+		/// Amounts paid to the author and to the beneficiary in the current block.
+		BlockRewards: (BalanceOf<T>, BalanceOf<T>);
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// Fees and tips of the block were paid out to the author, if known, and the beneficiary.
+		RewardsDistributed(Option<AccountId>, Balance, Balance),
+		/// The shares of fees and tips paid to the author were changed.
+		AuthorSharesChanged(Perbill, Perbill),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		fn on_initialize() -> Weight {
+			// Reading and removing `BlockRewards` and finding the author in `on_finalize`.
+			T::DbWeight::get().reads_writes(3, 1)
+		}
+
+		fn on_finalize() {
+			let (author_amount, beneficiary_amount) = <BlockRewards<T>>::take();
+
+			if !author_amount.is_zero() || !beneficiary_amount.is_zero() {
+				Self::deposit_event(RawEvent::RewardsDistributed(
+					Self::author(),
+					author_amount,
+					beneficiary_amount,
+				));
+			}
+		}
+
+		/// Set the shares of transaction fees and tips that are paid to the block author.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(2)]
+		pub fn set_author_shares(origin, fee_share: Perbill, tip_share: Perbill) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			FeeAuthorShare::put(fee_share);
+			TipAuthorShare::put(tip_share);
+			Self::deposit_event(RawEvent::AuthorSharesChanged(fee_share, tip_share));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The author of the current block, if it can be found.
+	pub fn author() -> Option<T::AccountId> {
+		let digest = frame_system::Module::<T>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+
+		T::FindAuthor::find_author(pre_runtime_digests)
+	}
+
+	fn distribute(fees: NegativeImbalanceOf<T>, tips: NegativeImbalanceOf<T>) {
+		let (to_author, to_beneficiary) = match Self::author() {
+			Some(author) => {
+				let fee_amount = Self::fee_author_share() * fees.peek();
+				let tip_amount = Self::tip_author_share() * tips.peek();
+				let (fees_to_author, fees_rest) = fees.split(fee_amount);
+				let (tips_to_author, tips_rest) = tips.split(tip_amount);
+				let to_author = fees_to_author.merge(tips_to_author);
+				let amount = to_author.peek();
+
+				<AuthorRewards<T>>::mutate(&author, |total| *total = total.saturating_add(amount));
+				T::Currency::resolve_creating(&author, to_author);
+
+				(amount, fees_rest.merge(tips_rest))
+			}
+			None => (Zero::zero(), fees.merge(tips)),
+		};
+
+		<BlockRewards<T>>::mutate(|(author_amount, beneficiary_amount)| {
+			*author_amount = author_amount.saturating_add(to_author);
+			*beneficiary_amount = beneficiary_amount.saturating_add(to_beneficiary.peek());
+		});
+		T::Beneficiary::on_unbalanced(to_beneficiary);
+	}
+}
+
+/// Expects the fees of a transaction first, followed by its tip.
+impl<T: Trait> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+	fn on_unbalanceds<B>(mut fees_then_tips: impl Iterator<Item = NegativeImbalanceOf<T>>)
+	where
+		NegativeImbalanceOf<T>: Imbalance<B>,
+	{
+		if let Some(fees) = fees_then_tips.next() {
+			let tips = fees_then_tips.fold(
+				<NegativeImbalanceOf<T> as Imbalance<BalanceOf<T>>>::zero(),
+				|tips, tip| <NegativeImbalanceOf<T> as Imbalance<BalanceOf<T>>>::merge(tips, tip),
+			);
+			Self::distribute(fees, tips);
+		}
+	}
+
+	fn on_nonzero_unbalanced(fees: NegativeImbalanceOf<T>) {
+		Self::distribute(fees, Imbalance::zero());
+	}
+}
@@ -0,0 +1,323 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that decides which accounts may collate for the parachain.
+//!
+//! The collator set of every session consists of a list of invulnerables, managed by
+//! `UpdateOrigin`, and of candidates that registered themselves by reserving `CandidacyBond`.
+//! Candidates that did not author a block within `KickThreshold` blocks are removed at the next
+//! session rotation and get their bond back.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	dispatch::DispatchResult,
+	ensure,
+	traits::{Currency, EnsureOrigin, Get, ReservableCurrency},
+	weights::Weight,
+};
+use frame_system::ensure_signed;
+use sp_runtime::{traits::Saturating, RuntimeDebug};
+use sp_staking::SessionIndex;
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// Tells whether an account has registered the keys it needs to author blocks.
+pub trait ValidatorRegistration<AccountId> {
+	/// Whether `who` has registered its keys.
+	fn is_registered(who: &AccountId) -> bool;
+}
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// A registered candidate and the bond it reserved.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct CandidateInfo<AccountId, Balance> {
+	/// Account of the candidate.
+	pub who: AccountId,
+	/// Amount reserved for the candidacy.
+	pub deposit: Balance,
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency the candidacy bond is reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// Origin that may change the invulnerables, the desired candidates and the bond.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Upper bound for `DesiredCandidates`.
+	type MaxCandidates: Get<u32>;
+
+	/// Maximum number of invulnerables.
+	type MaxInvulnerables: Get<u32>;
+
+	/// Number of blocks a candidate may go without authoring before it is kicked.
+	type KickThreshold: Get<Self::BlockNumber>;
+
+	/// Tells whether a candidate has registered its session keys.
+	type ValidatorRegistration: ValidatorRegistration<Self::AccountId>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as CollatorSelection {
+		/// Collators that are always part of the collator set.
+		Invulnerables get(fn invulnerables) config(): Vec<T::AccountId>;
+
+		/// Registered candidates, in order of registration.
+		Candidates get(fn candidates): Vec<CandidateInfo<T::AccountId, BalanceOf<T>>>;
+
+		/// Block number in which each candidate last authored a block.
+		LastAuthoredBlock get(fn last_authored_block):
+			map hasher(blake2_128_concat) T::AccountId => T::BlockNumber;
+
+		/// Number of candidates that may be registered at the same time.
+		DesiredCandidates get(fn desired_candidates) config(): u32;
+
+		/// Amount a candidate has to reserve to register.
+		CandidacyBond get(fn candidacy_bond) config(): BalanceOf<T>;
+	}
+	add_extra_genesis {
+		build(|config| {
+			assert!(
+				config.invulnerables.len() as u32 <= T::MaxInvulnerables::get(),
+				"Too many genesis invulnerables",
+			);
+			assert!(
+				!has_duplicates(&config.invulnerables),
+				"Duplicate genesis invulnerables",
+			);
+			assert!(
+				config.desired_candidates <= T::MaxCandidates::get(),
+				"Genesis desired candidates exceed `MaxCandidates`",
+			);
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// The list of invulnerables was replaced.
+		NewInvulnerables(Vec<AccountId>),
+		/// The number of desired candidates was changed.
+		NewDesiredCandidates(u32),
+		/// The candidacy bond was changed.
+		NewCandidacyBond(Balance),
+		/// An account registered as candidate, reserving the given bond.
+		CandidateAdded(AccountId, Balance),
+		/// A candidate left the candidate list.
+		CandidateRemoved(AccountId),
+		/// A candidate was removed because it stopped authoring blocks.
+		CandidateKicked(AccountId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// More invulnerables than `MaxInvulnerables`.
+		TooManyInvulnerables,
+		/// More desired candidates than `MaxCandidates`.
+		TooManyDesiredCandidates,
+		/// All candidate slots are taken.
+		TooManyCandidates,
+		/// The account is already a candidate.
+		AlreadyCandidate,
+		/// The account is already an invulnerable.
+		AlreadyInvulnerable,
+		/// The account is not a candidate.
+		NotCandidate,
+		/// The account has not registered its session keys.
+		ValidatorNotRegistered,
+		/// An account is listed twice.
+		DuplicateInvulnerable,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Upper bound for `DesiredCandidates`.
+		const MaxCandidates: u32 = T::MaxCandidates::get();
+
+		/// Maximum number of invulnerables.
+		const MaxInvulnerables: u32 = T::MaxInvulnerables::get();
+
+		/// Number of blocks a candidate may go without authoring before it is kicked.
+		const KickThreshold: T::BlockNumber = T::KickThreshold::get();
+
+		/// Replace the list of invulnerables.
+		///
+		/// Every invulnerable must have set its session keys, like a candidate.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(new.len() as Weight, 1)]
+		pub fn set_invulnerables(origin, new: Vec<T::AccountId>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(
+				new.len() as u32 <= T::MaxInvulnerables::get(),
+				Error::<T>::TooManyInvulnerables,
+			);
+			ensure!(!has_duplicates(&new), Error::<T>::DuplicateInvulnerable);
+			ensure!(
+				new.iter().all(T::ValidatorRegistration::is_registered),
+				Error::<T>::ValidatorNotRegistered,
+			);
+
+			<Invulnerables<T>>::put(&new);
+			Self::deposit_event(RawEvent::NewInvulnerables(new));
+		}
+
+		/// Set the number of candidates that may be registered at the same time.
+		///
+		/// Already registered candidates are not removed when lowering the number.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_desired_candidates(origin, max: u32) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+			ensure!(max <= T::MaxCandidates::get(), Error::<T>::TooManyDesiredCandidates);
+
+			DesiredCandidates::put(max);
+			Self::deposit_event(RawEvent::NewDesiredCandidates(max));
+		}
+
+		/// Set the amount new candidates have to reserve.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_candidacy_bond(origin, bond: BalanceOf<T>) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			<CandidacyBond<T>>::put(bond);
+			Self::deposit_event(RawEvent::NewCandidacyBond(bond));
+		}
+
+		/// Register the sender as candidate by reserving `CandidacyBond`.
+		///
+		/// The candidate joins the collator set at the next session rotation. It must have set
+		/// its session keys through the session pallet before.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(5, 3)]
+		pub fn register_as_candidate(origin) {
+			let who = ensure_signed(origin)?;
+			ensure!(!Self::invulnerables().contains(&who), Error::<T>::AlreadyInvulnerable);
+			ensure!(
+				T::ValidatorRegistration::is_registered(&who),
+				Error::<T>::ValidatorNotRegistered,
+			);
+
+			let deposit = Self::candidacy_bond();
+			<Candidates<T>>::try_mutate(|candidates| -> DispatchResult {
+				ensure!(
+					(candidates.len() as u32) < Self::desired_candidates(),
+					Error::<T>::TooManyCandidates,
+				);
+				ensure!(!candidates.iter().any(|c| c.who == who), Error::<T>::AlreadyCandidate);
+
+				T::Currency::reserve(&who, deposit)?;
+				candidates.push(CandidateInfo { who: who.clone(), deposit });
+				Ok(())
+			})?;
+
+			<LastAuthoredBlock<T>>::insert(&who, frame_system::Module::<T>::block_number());
+			Self::deposit_event(RawEvent::CandidateAdded(who, deposit));
+		}
+
+		/// Remove the sender from the candidates and unreserve its bond.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(1, 3)]
+		pub fn leave_intent(origin) {
+			let who = ensure_signed(origin)?;
+
+			Self::try_remove_candidate(&who)?;
+			Self::deposit_event(RawEvent::CandidateRemoved(who));
+		}
+	}
+}
+
+/// Whether an account appears more than once in `accounts`.
+fn has_duplicates<AccountId: Ord + Clone>(accounts: &[AccountId]) -> bool {
+	let mut sorted = accounts.to_vec();
+	sorted.sort();
+	sorted.dedup();
+	sorted.len() != accounts.len()
+}
+
+impl<T: Trait> Module<T> {
+	fn try_remove_candidate(who: &T::AccountId) -> DispatchResult {
+		<Candidates<T>>::try_mutate(|candidates| -> DispatchResult {
+			let index = candidates
+				.iter()
+				.position(|c| &c.who == who)
+				.ok_or(Error::<T>::NotCandidate)?;
+			let candidate = candidates.remove(index);
+
+			T::Currency::unreserve(who, candidate.deposit);
+			<LastAuthoredBlock<T>>::remove(who);
+			Ok(())
+		})
+	}
+
+	/// Remove all candidates that did not author a block within `KickThreshold` blocks.
+	fn kick_stale_candidates() -> Vec<T::AccountId> {
+		let now = frame_system::Module::<T>::block_number();
+		let threshold = T::KickThreshold::get();
+
+		let (active, stale): (Vec<_>, Vec<_>) = Self::candidates().into_iter().partition(|c| {
+			now.saturating_sub(Self::last_authored_block(&c.who)) < threshold
+		});
+
+		for candidate in stale {
+			T::Currency::unreserve(&candidate.who, candidate.deposit);
+			<LastAuthoredBlock<T>>::remove(&candidate.who);
+			Self::deposit_event(RawEvent::CandidateKicked(candidate.who));
+		}
+
+		let collators = active.iter().map(|c| c.who.clone()).collect();
+		<Candidates<T>>::put(active);
+		collators
+	}
+
+	/// The collator set for the next session: the invulnerables followed by the candidates.
+	fn assemble_collators() -> Vec<T::AccountId> {
+		let mut collators = Self::invulnerables();
+		for candidate in Self::kick_stale_candidates() {
+			if !collators.contains(&candidate) {
+				collators.push(candidate);
+			}
+		}
+		collators
+	}
+}
+
+impl<T: Trait> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Module<T> {
+	fn note_author(author: T::AccountId) {
+		<LastAuthoredBlock<T>>::insert(author, frame_system::Module::<T>::block_number());
+	}
+
+	fn note_uncle(_author: T::AccountId, _age: T::BlockNumber) {}
+}
+
+impl<T: Trait> pallet_session::SessionManager<T::AccountId> for Module<T> {
+	fn new_session(_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+		let collators = Self::assemble_collators();
+
+		// An empty set would halt block production, keep the current one instead.
+		if collators.is_empty() {
+			None
+		} else {
+			Some(collators)
+		}
+	}
+
+	fn start_session(_index: SessionIndex) {}
+
+	fn end_session(_index: SessionIndex) {}
+}
@@ -0,0 +1,357 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the invulnerables, the candidacy bond and the election of collators.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types, weights::Weight,
+};
+use frame_system::RawOrigin;
+use pallet_authorship::EventHandler;
+use pallet_session::SessionManager;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod collator_selection {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		collator_selection<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+/// Every account but `NO_KEYS` has set its session keys.
+pub struct TestRegistration;
+
+impl ValidatorRegistration<u64> for TestRegistration {
+	fn is_registered(who: &u64) -> bool {
+		*who != NO_KEYS
+	}
+}
+
+parameter_types! {
+	pub const MaxCandidates: u32 = 3;
+	pub const MaxInvulnerables: u32 = 2;
+	pub const KickThreshold: u64 = 10;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Currency = Balances;
+	type UpdateOrigin = frame_system::EnsureRoot<u64>;
+	type MaxCandidates = MaxCandidates;
+	type MaxInvulnerables = MaxInvulnerables;
+	type KickThreshold = KickThreshold;
+	type ValidatorRegistration = TestRegistration;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type CollatorSelection = Module<Test>;
+type BalancesError = pallet_balances::Error<Test, pallet_balances::DefaultInstance>;
+
+const INVULNERABLE: u64 = 1;
+const ALICE: u64 = 2;
+const BOB: u64 = 3;
+const CHARLIE: u64 = 4;
+const NO_KEYS: u64 = 5;
+const BOND: u64 = 10;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 100), (BOB, 100), (CHARLIE, 5), (NO_KEYS, 100)],
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+	GenesisConfig::<Test> {
+		invulnerables: vec![INVULNERABLE],
+		desired_candidates: 2,
+		candidacy_bond: BOND,
+	}
+	.assimilate_storage(&mut t)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn set_invulnerables_replaces_the_list() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorSelection::set_invulnerables(Origin::signed(ALICE), vec![ALICE]),
+			BadOrigin,
+		);
+
+		assert_ok!(CollatorSelection::set_invulnerables(
+			RawOrigin::Root.into(),
+			vec![ALICE, BOB],
+		));
+
+		assert_eq!(CollatorSelection::invulnerables(), vec![ALICE, BOB]);
+		assert_eq!(
+			last_event(),
+			TestEvent::collator_selection(RawEvent::NewInvulnerables(vec![ALICE, BOB])),
+		);
+	});
+}
+
+#[test]
+fn set_invulnerables_respects_the_maximum() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorSelection::set_invulnerables(
+				RawOrigin::Root.into(),
+				vec![ALICE, BOB, CHARLIE],
+			),
+			Error::<Test>::TooManyInvulnerables,
+		);
+	});
+}
+
+#[test]
+fn set_invulnerables_rejects_duplicates() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorSelection::set_invulnerables(RawOrigin::Root.into(), vec![ALICE, ALICE]),
+			Error::<Test>::DuplicateInvulnerable,
+		);
+	});
+}
+
+#[test]
+fn set_invulnerables_requires_session_keys() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorSelection::set_invulnerables(RawOrigin::Root.into(), vec![ALICE, NO_KEYS]),
+			Error::<Test>::ValidatorNotRegistered,
+		);
+	});
+}
+
+#[test]
+fn register_as_candidate_reserves_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+
+		assert_eq!(
+			CollatorSelection::candidates(),
+			vec![CandidateInfo { who: ALICE, deposit: BOND }],
+		);
+		assert_eq!(Balances::reserved_balance(ALICE), BOND);
+		assert_eq!(CollatorSelection::last_authored_block(ALICE), 1);
+		assert_eq!(
+			last_event(),
+			TestEvent::collator_selection(RawEvent::CandidateAdded(ALICE, BOND)),
+		);
+	});
+}
+
+#[test]
+fn register_as_candidate_checks_the_candidate() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			CollatorSelection::register_as_candidate(Origin::signed(INVULNERABLE)),
+			Error::<Test>::AlreadyInvulnerable,
+		);
+		assert_noop!(
+			CollatorSelection::register_as_candidate(Origin::signed(NO_KEYS)),
+			Error::<Test>::ValidatorNotRegistered,
+		);
+		assert_noop!(
+			CollatorSelection::register_as_candidate(Origin::signed(CHARLIE)),
+			BalancesError::InsufficientBalance,
+		);
+
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+		assert_noop!(
+			CollatorSelection::register_as_candidate(Origin::signed(ALICE)),
+			Error::<Test>::AlreadyCandidate,
+		);
+	});
+}
+
+#[test]
+fn candidates_are_limited_by_desired_candidates() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::set_desired_candidates(RawOrigin::Root.into(), 1));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+
+		assert_noop!(
+			CollatorSelection::register_as_candidate(Origin::signed(BOB)),
+			Error::<Test>::TooManyCandidates,
+		);
+		assert_noop!(
+			CollatorSelection::set_desired_candidates(RawOrigin::Root.into(), 4),
+			Error::<Test>::TooManyDesiredCandidates,
+		);
+	});
+}
+
+#[test]
+fn leave_intent_unreserves_the_bond() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+
+		assert_ok!(CollatorSelection::leave_intent(Origin::signed(ALICE)));
+
+		assert!(CollatorSelection::candidates().is_empty());
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 100);
+		assert_noop!(
+			CollatorSelection::leave_intent(Origin::signed(ALICE)),
+			Error::<Test>::NotCandidate,
+		);
+	});
+}
+
+#[test]
+fn bond_changes_apply_to_new_candidates_only() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+		assert_ok!(CollatorSelection::set_candidacy_bond(RawOrigin::Root.into(), 20));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(BOB)));
+
+		assert_eq!(Balances::reserved_balance(BOB), 20);
+
+		assert_ok!(CollatorSelection::leave_intent(Origin::signed(ALICE)));
+		assert_eq!(Balances::free_balance(ALICE), 100);
+	});
+}
+
+#[test]
+fn new_session_elects_invulnerables_then_candidates() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(BOB)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+
+		assert_eq!(CollatorSelection::new_session(1), Some(vec![INVULNERABLE, BOB, ALICE]));
+	});
+}
+
+#[test]
+fn candidate_made_invulnerable_is_elected_once() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+		assert_ok!(CollatorSelection::set_invulnerables(
+			RawOrigin::Root.into(),
+			vec![INVULNERABLE, ALICE],
+		));
+
+		assert_eq!(CollatorSelection::new_session(1), Some(vec![INVULNERABLE, ALICE]));
+	});
+}
+
+#[test]
+fn empty_collator_set_keeps_the_current_one() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::set_invulnerables(RawOrigin::Root.into(), vec![]));
+
+		assert_eq!(CollatorSelection::new_session(1), None);
+	});
+}
+
+#[test]
+fn stale_candidates_are_kicked_and_refunded() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(BOB)));
+
+		System::set_block_number(8);
+		CollatorSelection::note_author(BOB);
+		System::set_block_number(11);
+
+		assert_eq!(CollatorSelection::new_session(1), Some(vec![INVULNERABLE, BOB]));
+		assert_eq!(
+			CollatorSelection::candidates(),
+			vec![CandidateInfo { who: BOB, deposit: BOND }],
+		);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 100);
+		assert_eq!(Balances::reserved_balance(BOB), BOND);
+		assert!(System::events().iter().any(|r| {
+			r.event == TestEvent::collator_selection(RawEvent::CandidateKicked(ALICE))
+		}));
+	});
+}
+
+#[test]
+fn candidates_within_the_threshold_are_kept() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(CollatorSelection::register_as_candidate(Origin::signed(ALICE)));
+
+		System::set_block_number(10);
+
+		assert_eq!(CollatorSelection::new_session(1), Some(vec![INVULNERABLE, ALICE]));
+		assert_eq!(Balances::reserved_balance(ALICE), BOND);
+	});
+}
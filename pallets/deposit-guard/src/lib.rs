@@ -0,0 +1,410 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that limits the transfers received from sibling parachains.
+//!
+//! Limits apply per local asset, `None` being the native token. Root may restrict an asset to an
+//! allowlist of origin parachains, and limit the amount received within `RateLimitWindow` blocks
+//! from every origin and from all origins together. Transfers from other origins or above a
+//! limit are quarantined. Root may release quarantined transfers to their beneficiary or send
+//! them back.
+//!
+//! The amount received within the window is estimated from the amount received in the current
+//! and previous window periods, the previous one weighted by the part of it still inside the
+//! window.
+//!
+//! Transfers of assets the registry does not know yet are quarantined while its policy is
+//! `Reject`, so that root may register the asset and release them. Under `AutoCreate` they are
+//! admitted and create their asset, whose limits apply to later transfers.
+//!
+//! At most `MaxQuarantinedPerOrigin` transfers of an origin are quarantined at a time. Further
+//! transfers of that origin that would be quarantined are sent back, or dropped with a `Dropped`
+//! event if they cannot be sent. While outbound transfers are paused nothing can be sent back, so
+//! they are quarantined regardless of the cap.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives::ParaId;
+use frame_support::{decl_error, decl_event, decl_module, decl_storage, traits::Get};
+use frame_system::ensure_root;
+use pallet_asset_dealer::DepositFilter;
+use pallet_asset_registry::RegistrationPolicy;
+use parachain_primitives::MultiAsset;
+use sp_runtime::{
+	traits::{Saturating, UniqueSaturatedInto, Zero},
+	Perbill, RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+type AssetsOf<T> = <T as pallet_asset_registry::Trait>::Assets;
+type AssetIdOf<T> = <AssetsOf<T> as MultiAsset<<T as frame_system::Trait>::AccountId>>::AssetId;
+type AssetBalanceOf<T> =
+	<AssetsOf<T> as MultiAsset<<T as frame_system::Trait>::AccountId>>::Balance;
+type AssetDealer<T> = pallet_asset_dealer::Module<T>;
+type AssetRegistry<T> = pallet_asset_registry::Module<T>;
+type UsageOf<T> = WindowUsage<<T as frame_system::Trait>::BlockNumber, AssetBalanceOf<T>>;
+type QuarantinedOf<T> =
+	QuarantinedDeposit<<T as frame_system::Trait>::AccountId, AssetIdOf<T>, AssetBalanceOf<T>>;
+
+/// Identifier of a quarantined transfer.
+pub type QuarantineId = u64;
+
+/// Why a transfer was quarantined.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum QuarantineReason {
+	/// The origin is not on the allowlist of the asset.
+	Untrusted,
+	/// The limit of the origin for the asset was hit.
+	OriginLimit,
+	/// The limit of the asset was hit.
+	AssetLimit,
+	/// The asset is neither registered nor a local asset, and the registry rejects unknown assets.
+	UnknownAsset,
+}
+
+/// A received transfer held back by a limit.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct QuarantinedDeposit<AccountId, AssetId, Balance> {
+	/// The parachain that sent it.
+	pub origin: ParaId,
+	/// The asset as identified in the message, `None` for the native token.
+	pub asset_id: Option<AssetId>,
+	/// The account it was sent to.
+	pub beneficiary: AccountId,
+	/// The amount sent.
+	pub amount: Balance,
+}
+
+/// Amounts received in the current and the previous window period.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct WindowUsage<BlockNumber, Balance> {
+	/// The current window period.
+	pub period: BlockNumber,
+	/// Amount received in the current period.
+	pub current: Balance,
+	/// Amount received in the previous period.
+	pub previous: Balance,
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: pallet_asset_dealer::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// Number of blocks the limits apply to.
+	type RateLimitWindow: Get<Self::BlockNumber>;
+
+	/// Maximum number of quarantined transfers of a single origin.
+	type MaxQuarantinedPerOrigin: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as DepositGuard {
+		/// The only parachains transfers of an asset are accepted from, if restricted.
+		Allowlists get(fn allowlist): map hasher(twox_64_concat) Option<AssetIdOf<T>>
+			=> Option<Vec<ParaId>>;
+
+		/// Maximum amount of an asset received from a parachain within the window.
+		OriginLimits get(fn origin_limit): double_map hasher(twox_64_concat) ParaId,
+			hasher(twox_64_concat) Option<AssetIdOf<T>> => Option<AssetBalanceOf<T>>;
+
+		/// Maximum amount of an asset received from all parachains within the window.
+		AssetLimits get(fn asset_limit):
+			map hasher(twox_64_concat) Option<AssetIdOf<T>> => Option<AssetBalanceOf<T>>;
+
+		/// Amounts of an asset received from a parachain.
+		OriginUsage: double_map hasher(twox_64_concat) ParaId,
+			hasher(twox_64_concat) Option<AssetIdOf<T>> => UsageOf<T>;
+
+		/// Amounts of an asset received from all parachains.
+		AssetUsage: map hasher(twox_64_concat) Option<AssetIdOf<T>> => UsageOf<T>;
+
+		/// The quarantined transfers.
+		Quarantine get(fn quarantined):
+			map hasher(twox_64_concat) QuarantineId => Option<QuarantinedOf<T>>;
+
+		/// The identifier of the next quarantined transfer.
+		NextQuarantineId: QuarantineId;
+
+		/// Number of quarantined transfers by origin.
+		QuarantinedCount get(fn quarantined_count): map hasher(twox_64_concat) ParaId => u32;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		AssetId = AssetIdOf<T>,
+		AssetBalance = AssetBalanceOf<T>,
+	{
+		/// A transfer of the asset from the parachain to the account was quarantined.
+		Quarantined(
+			QuarantineId,
+			QuarantineReason,
+			ParaId,
+			Option<AssetId>,
+			AccountId,
+			AssetBalance,
+		),
+		/// A quarantined transfer was released to its beneficiary.
+		Released(QuarantineId),
+		/// A quarantined transfer was sent back.
+		Returned(QuarantineId),
+		/// A transfer of the asset from the parachain to the account could neither be
+		/// quarantined, because the quarantine of the parachain is full, nor sent back.
+		Dropped(ParaId, Option<AssetId>, AccountId, AssetBalance),
+		/// The allowlist of a local asset was set, or removed if `None`.
+		AllowlistSet(Option<AssetId>, Option<Vec<ParaId>>),
+		/// The limit of the parachain for a local asset was set, or removed if `None`.
+		OriginLimitSet(ParaId, Option<AssetId>, Option<AssetBalance>),
+		/// The limit of a local asset was set, or removed if `None`.
+		AssetLimitSet(Option<AssetId>, Option<AssetBalance>),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// No transfer is quarantined with the given identifier.
+		UnknownQuarantine,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Number of blocks the limits apply to.
+		const RateLimitWindow: T::BlockNumber = T::RateLimitWindow::get();
+
+		/// Maximum number of quarantined transfers of a single origin.
+		const MaxQuarantinedPerOrigin: u32 = T::MaxQuarantinedPerOrigin::get();
+
+		/// Accept transfers of a local asset, `None` for the native token, only from the given
+		/// parachains, or from all if `None`.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_allowlist(
+			origin,
+			asset: Option<AssetIdOf<T>>,
+			origins: Option<Vec<ParaId>>,
+		) {
+			ensure_root(origin)?;
+
+			match &origins {
+				Some(origins) => <Allowlists<T>>::insert(asset, origins),
+				None => <Allowlists<T>>::remove(asset),
+			}
+			Self::deposit_event(RawEvent::AllowlistSet(asset, origins));
+		}
+
+		/// Limit the amount of a local asset received from `para_id` within the window.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_origin_limit(
+			origin,
+			para_id: ParaId,
+			asset: Option<AssetIdOf<T>>,
+			limit: Option<AssetBalanceOf<T>>,
+		) {
+			ensure_root(origin)?;
+
+			match limit {
+				Some(limit) => <OriginLimits<T>>::insert(para_id, asset, limit),
+				None => <OriginLimits<T>>::remove(para_id, asset),
+			}
+			Self::deposit_event(RawEvent::OriginLimitSet(para_id, asset, limit));
+		}
+
+		/// Limit the amount of a local asset received from all parachains within the window.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_asset_limit(
+			origin,
+			asset: Option<AssetIdOf<T>>,
+			limit: Option<AssetBalanceOf<T>>,
+		) {
+			ensure_root(origin)?;
+
+			match limit {
+				Some(limit) => <AssetLimits<T>>::insert(asset, limit),
+				None => <AssetLimits<T>>::remove(asset),
+			}
+			Self::deposit_event(RawEvent::AssetLimitSet(asset, limit));
+		}
+
+		/// Deposit a quarantined transfer into its beneficiary, ignoring the limits.
+		#[weight = 100_000_000 + T::DbWeight::get().reads_writes(7, 6)]
+		pub fn release(origin, quarantine_id: QuarantineId) {
+			ensure_root(origin)?;
+
+			let deposit = <Quarantine<T>>::take(quarantine_id)
+				.ok_or(Error::<T>::UnknownQuarantine)?;
+			Self::unquarantined(deposit.origin);
+			AssetDealer::<T>::deposit_admitted(
+				deposit.origin,
+				deposit.asset_id,
+				deposit.beneficiary,
+				deposit.amount,
+			);
+
+			Self::deposit_event(RawEvent::Released(quarantine_id));
+		}
+
+		/// Send a quarantined transfer back to its beneficiary on the parachain it came from.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(3, 3)]
+		pub fn send_back(origin, quarantine_id: QuarantineId) {
+			ensure_root(origin)?;
+
+			let deposit = Self::quarantined(quarantine_id).ok_or(Error::<T>::UnknownQuarantine)?;
+			AssetDealer::<T>::send_back(
+				deposit.origin,
+				deposit.asset_id,
+				deposit.beneficiary,
+				deposit.amount,
+			)?;
+			<Quarantine<T>>::remove(quarantine_id);
+			Self::unquarantined(deposit.origin);
+
+			Self::deposit_event(RawEvent::Returned(quarantine_id));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// `usage` moved to the current window period, and the amount received within the window.
+	fn in_window(mut usage: UsageOf<T>) -> (UsageOf<T>, AssetBalanceOf<T>) {
+		let window = T::RateLimitWindow::get();
+		if window.is_zero() {
+			return (UsageOf::<T>::default(), Zero::zero());
+		}
+
+		let now = <frame_system::Module<T>>::block_number();
+		let period = now / window;
+		if period != usage.period {
+			let previous = if period == usage.period.saturating_add(1u32.into()) {
+				usage.current
+			} else {
+				Zero::zero()
+			};
+			usage = WindowUsage { period, current: Zero::zero(), previous };
+		}
+
+		let elapsed: u32 = (now % window).unique_saturated_into();
+		let window: u32 = window.unique_saturated_into();
+		let previous_share = Perbill::from_rational_approximation(window - elapsed, window);
+		let received = usage.current.saturating_add(previous_share * usage.previous);
+
+		(usage, received)
+	}
+
+	/// Why a transfer may not be deposited, if so. Records the amount if it may.
+	fn check(
+		para_id: ParaId,
+		asset: Option<AssetIdOf<T>>,
+		amount: AssetBalanceOf<T>,
+	) -> Option<QuarantineReason> {
+		if let Some(origins) = Self::allowlist(asset) {
+			if !origins.contains(&para_id) {
+				return Some(QuarantineReason::Untrusted);
+			}
+		}
+
+		let (mut origin_usage, received) = Self::in_window(<OriginUsage<T>>::get(para_id, asset));
+		if let Some(limit) = Self::origin_limit(para_id, asset) {
+			if received.saturating_add(amount) > limit {
+				return Some(QuarantineReason::OriginLimit);
+			}
+		}
+
+		let (mut asset_usage, received) = Self::in_window(<AssetUsage<T>>::get(asset));
+		if let Some(limit) = Self::asset_limit(asset) {
+			if received.saturating_add(amount) > limit {
+				return Some(QuarantineReason::AssetLimit);
+			}
+		}
+
+		origin_usage.current = origin_usage.current.saturating_add(amount);
+		asset_usage.current = asset_usage.current.saturating_add(amount);
+		<OriginUsage<T>>::insert(para_id, asset, origin_usage);
+		<AssetUsage<T>>::insert(asset, asset_usage);
+
+		None
+	}
+
+	/// Quarantine a transfer, or send it back if the quarantine of its origin is full and
+	/// outbound transfers are not paused.
+	fn quarantine(
+		reason: QuarantineReason,
+		para_id: ParaId,
+		asset_id: Option<AssetIdOf<T>>,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) {
+		let count = Self::quarantined_count(para_id);
+		if count >= T::MaxQuarantinedPerOrigin::get() && !T::OutboundPaused::get() {
+			if AssetDealer::<T>::send_back(para_id, asset_id, dest.clone(), amount).is_err() {
+				Self::deposit_event(RawEvent::Dropped(para_id, asset_id, dest.clone(), amount));
+			}
+			return;
+		}
+
+		let quarantine_id = NextQuarantineId::mutate(|id| {
+			let quarantine_id = *id;
+			*id = id.wrapping_add(1);
+			quarantine_id
+		});
+		<Quarantine<T>>::insert(quarantine_id, QuarantinedDeposit {
+			origin: para_id,
+			asset_id,
+			beneficiary: dest.clone(),
+			amount,
+		});
+		QuarantinedCount::insert(para_id, count.saturating_add(1));
+		Self::deposit_event(RawEvent::Quarantined(
+			quarantine_id,
+			reason,
+			para_id,
+			asset_id,
+			dest.clone(),
+			amount,
+		));
+	}
+
+	fn unquarantined(para_id: ParaId) {
+		match Self::quarantined_count(para_id) {
+			0 | 1 => QuarantinedCount::remove(para_id),
+			count => QuarantinedCount::insert(para_id, count - 1),
+		}
+	}
+}
+
+impl<T: Trait> DepositFilter<T::AccountId, AssetIdOf<T>, AssetBalanceOf<T>> for Module<T> {
+	fn admit(
+		para_id: ParaId,
+		asset_id: Option<AssetIdOf<T>>,
+		dest: &T::AccountId,
+		amount: AssetBalanceOf<T>,
+	) -> bool {
+		let reason = match asset_id {
+			Some(asset_id) => match AssetDealer::<T>::local_asset(para_id, asset_id) {
+				Some(local_id) => Self::check(para_id, Some(local_id), amount),
+				None => match AssetRegistry::<T>::policy() {
+					RegistrationPolicy::Reject => Some(QuarantineReason::UnknownAsset),
+					RegistrationPolicy::AutoCreate => None,
+				},
+			},
+			None => Self::check(para_id, None, amount),
+		};
+
+		match reason {
+			Some(reason) => {
+				Self::quarantine(reason, para_id, asset_id, dest, amount);
+				false
+			}
+			None => true,
+		}
+	}
+}
@@ -0,0 +1,482 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the deposit limits and the quarantine.
+
+use super::*;
+use cumulus_primitives::xcmp::XCMPMessageSender;
+use frame_support::{
+	assert_noop, assert_ok, dispatch::DispatchResult, impl_outer_event, impl_outer_origin,
+	parameter_types, weights::Weight,
+};
+use frame_system::RawOrigin;
+use pallet_asset_registry::{AssetIdAllocator, RegistrationPolicy};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	DispatchError,
+};
+use std::{cell::RefCell, collections::HashMap};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod deposit_guard {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		pallet_asset_registry<T>,
+		pallet_asset_dealer<T>,
+		deposit_guard<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 10;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static ASSETS: RefCell<HashMap<(u32, u64), u64>> = RefCell::new(HashMap::new());
+	static NEXT_ASSET_ID: RefCell<u32> = RefCell::new(100);
+	static SENT: RefCell<Vec<ParaId>> = RefCell::new(Vec::new());
+	static SEND_FAILS: RefCell<bool> = RefCell::new(false);
+	static OUTBOUND_PAUSED: RefCell<bool> = RefCell::new(false);
+}
+
+/// Assets kept in a map, without total supply.
+pub struct TestAssets;
+
+impl MultiAsset<u64> for TestAssets {
+	type AssetId = u32;
+	type Balance = u64;
+
+	fn balance(asset_id: u32, who: &u64) -> u64 {
+		ASSETS.with(|a| a.borrow().get(&(asset_id, *who)).copied().unwrap_or_default())
+	}
+
+	fn total_supply(_asset_id: u32) -> u64 {
+		0
+	}
+
+	fn transfer(asset_id: u32, from: &u64, to: &u64, amount: u64) -> DispatchResult {
+		Self::withdraw(asset_id, from, amount)?;
+		Self::deposit(asset_id, to, amount)
+	}
+
+	fn deposit(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who) + amount;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+
+	fn withdraw(asset_id: u32, who: &u64, amount: u64) -> DispatchResult {
+		let balance = Self::balance(asset_id, who)
+			.checked_sub(amount)
+			.ok_or(DispatchError::Other("Insufficient asset balance"))?;
+		ASSETS.with(|a| a.borrow_mut().insert((asset_id, *who), balance));
+		Ok(())
+	}
+}
+
+/// Hands out increasing asset ids.
+pub struct NextAssetId;
+
+impl AssetIdAllocator<u32> for NextAssetId {
+	fn allocate() -> u32 {
+		NEXT_ASSET_ID.with(|next| {
+			let id = *next.borrow();
+			*next.borrow_mut() += 1;
+			id
+		})
+	}
+
+	fn is_allocated(asset_id: u32) -> bool {
+		NEXT_ASSET_ID.with(|next| asset_id < *next.borrow())
+	}
+
+	fn reserve(asset_id: u32) {
+		NEXT_ASSET_ID.with(|next| {
+			let mut next = next.borrow_mut();
+			*next = (*next).max(asset_id + 1);
+		})
+	}
+}
+
+/// Records the destinations of the sent messages, or fails to send them while `SEND_FAILS` is
+/// set.
+pub struct TestSender;
+
+impl<M> XCMPMessageSender<M> for TestSender {
+	fn send_xcmp_message(dest: ParaId, _msg: &M) -> Result<(), ()> {
+		if SEND_FAILS.with(|fails| *fails.borrow()) {
+			return Err(());
+		}
+		SENT.with(|sent| sent.borrow_mut().push(dest));
+		Ok(())
+	}
+}
+
+/// `true` while `OUTBOUND_PAUSED` is set.
+pub struct OutboundPaused;
+
+impl Get<bool> for OutboundPaused {
+	fn get() -> bool {
+		OUTBOUND_PAUSED.with(|paused| *paused.borrow())
+	}
+}
+
+impl pallet_asset_registry::Trait for Test {
+	type Event = TestEvent;
+	type Assets = TestAssets;
+	type AssetIdAllocator = NextAssetId;
+	type RegisterOrigin = frame_system::EnsureRoot<u64>;
+}
+
+impl pallet_asset_dealer::Trait for Test {
+	type Event = TestEvent;
+	type XCMPMessageSender = TestSender;
+	type Currency = Balances;
+	type UpdateOrigin = frame_system::EnsureRoot<u64>;
+	type DepositFilter = DepositGuard;
+	type OutboundPaused = OutboundPaused;
+}
+
+parameter_types! {
+	pub const RateLimitWindow: u64 = 10;
+	pub const MaxQuarantinedPerOrigin: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type RateLimitWindow = RateLimitWindow;
+	type MaxQuarantinedPerOrigin = MaxQuarantinedPerOrigin;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type Registry = pallet_asset_registry::Module<Test>;
+type Dealer = pallet_asset_dealer::Module<Test>;
+type DepositGuard = Module<Test>;
+
+const ALICE: u64 = 1;
+const SIBLING: u32 = 200;
+const OTHER_SIBLING: u32 = 300;
+const REMOTE_ASSET: u32 = 7;
+const LOCAL_ASSET: u32 = 3;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	ASSETS.with(|a| a.borrow_mut().clear());
+	NEXT_ASSET_ID.with(|next| *next.borrow_mut() = 100);
+	SENT.with(|sent| sent.borrow_mut().clear());
+	SEND_FAILS.with(|fails| *fails.borrow_mut() = false);
+	OUTBOUND_PAUSED.with(|paused| *paused.borrow_mut() = false);
+
+	let storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Registry::register(
+			RawOrigin::Root.into(),
+			sibling(),
+			REMOTE_ASSET,
+			Some(LOCAL_ASSET),
+		));
+	});
+	ext
+}
+
+fn sibling() -> ParaId {
+	ParaId::from(SIBLING)
+}
+
+fn other_sibling() -> ParaId {
+	ParaId::from(OTHER_SIBLING)
+}
+
+fn sent() -> Vec<ParaId> {
+	SENT.with(|sent| sent.borrow().clone())
+}
+
+fn events() -> Vec<TestEvent> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
+
+fn quarantined(
+	quarantine_id: QuarantineId,
+	reason: QuarantineReason,
+	para_id: ParaId,
+	asset_id: Option<u32>,
+	amount: u64,
+) -> TestEvent {
+	TestEvent::deposit_guard(RawEvent::Quarantined(
+		quarantine_id,
+		reason,
+		para_id,
+		asset_id,
+		ALICE,
+		amount,
+	))
+}
+
+#[test]
+fn origin_limit_quarantines_the_excess() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_origin_limit(
+			RawOrigin::Root.into(),
+			sibling(),
+			Some(LOCAL_ASSET),
+			Some(100),
+		));
+
+		Dealer::receive(sibling(), REMOTE_ASSET, ALICE, 60);
+		assert_eq!(TestAssets::balance(LOCAL_ASSET, &ALICE), 60);
+
+		Dealer::receive(sibling(), REMOTE_ASSET, ALICE, 50);
+		assert_eq!(TestAssets::balance(LOCAL_ASSET, &ALICE), 60);
+		assert_eq!(
+			DepositGuard::quarantined(0),
+			Some(QuarantinedDeposit {
+				origin: sibling(),
+				asset_id: Some(REMOTE_ASSET),
+				beneficiary: ALICE,
+				amount: 50,
+			}),
+		);
+		let event =
+			quarantined(0, QuarantineReason::OriginLimit, sibling(), Some(REMOTE_ASSET), 50);
+		assert!(events().contains(&event));
+	});
+}
+
+#[test]
+fn asset_limit_applies_to_all_origins() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_asset_limit(RawOrigin::Root.into(), None, Some(100)));
+
+		assert!(Dealer::receive_native(sibling(), &ALICE, 60));
+		assert!(!Dealer::receive_native(other_sibling(), &ALICE, 50));
+
+		let event = quarantined(0, QuarantineReason::AssetLimit, other_sibling(), None, 50);
+		assert!(events().contains(&event));
+		assert!(Dealer::receive_native(other_sibling(), &ALICE, 40));
+	});
+}
+
+#[test]
+fn origin_off_the_allowlist_is_quarantined() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_allowlist(
+			RawOrigin::Root.into(),
+			None,
+			Some(vec![sibling()]),
+		));
+
+		assert!(Dealer::receive_native(sibling(), &ALICE, 50));
+		assert!(!Dealer::receive_native(other_sibling(), &ALICE, 50));
+
+		let event = quarantined(0, QuarantineReason::Untrusted, other_sibling(), None, 50);
+		assert!(events().contains(&event));
+	});
+}
+
+#[test]
+fn limit_frees_up_as_the_window_moves() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_origin_limit(
+			RawOrigin::Root.into(),
+			sibling(),
+			None,
+			Some(100),
+		));
+		assert!(Dealer::receive_native(sibling(), &ALICE, 100));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 1));
+
+		// Half of the previous period is still inside the window.
+		System::set_block_number(15);
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 51));
+		assert!(Dealer::receive_native(sibling(), &ALICE, 50));
+
+		// The previous period is empty, the one before is out of the window.
+		System::set_block_number(30);
+		assert!(Dealer::receive_native(sibling(), &ALICE, 100));
+	});
+}
+
+#[test]
+fn unknown_asset_is_quarantined_until_registered_under_reject() {
+	new_test_ext().execute_with(|| {
+		Dealer::receive(other_sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_eq!(Registry::local_asset_id(other_sibling(), REMOTE_ASSET), None);
+		let event =
+			quarantined(0, QuarantineReason::UnknownAsset, other_sibling(), Some(REMOTE_ASSET), 50);
+		assert!(events().contains(&event));
+
+		assert_ok!(Registry::register(RawOrigin::Root.into(), other_sibling(), REMOTE_ASSET, None));
+		assert_ok!(DepositGuard::release(RawOrigin::Root.into(), 0));
+
+		assert_eq!(TestAssets::balance(100, &ALICE), 50);
+		assert_eq!(DepositGuard::quarantined(0), None);
+		assert_eq!(DepositGuard::quarantined_count(other_sibling()), 0);
+	});
+}
+
+#[test]
+fn unknown_asset_is_created_under_auto_create_and_then_limited() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Registry::set_policy(RawOrigin::Root.into(), RegistrationPolicy::AutoCreate));
+
+		Dealer::receive(other_sibling(), REMOTE_ASSET, ALICE, 50);
+
+		assert_eq!(Registry::local_asset_id(other_sibling(), REMOTE_ASSET), Some(100));
+		assert_eq!(TestAssets::balance(100, &ALICE), 50);
+		assert_eq!(DepositGuard::quarantined(0), None);
+
+		assert_ok!(DepositGuard::set_origin_limit(
+			RawOrigin::Root.into(),
+			other_sibling(),
+			Some(100),
+			Some(10),
+		));
+		Dealer::receive(other_sibling(), REMOTE_ASSET, ALICE, 20);
+
+		assert_eq!(TestAssets::balance(100, &ALICE), 50);
+		let event =
+			quarantined(0, QuarantineReason::OriginLimit, other_sibling(), Some(REMOTE_ASSET), 20);
+		assert!(events().contains(&event));
+	});
+}
+
+#[test]
+fn full_quarantine_sends_transfers_back() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_allowlist(RawOrigin::Root.into(), None, Some(vec![])));
+
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+		assert_eq!(DepositGuard::quarantined_count(sibling()), 2);
+		assert!(sent().is_empty());
+
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+		assert_eq!(sent(), vec![sibling()]);
+		assert_eq!(DepositGuard::quarantined(2), None);
+
+		SEND_FAILS.with(|fails| *fails.borrow_mut() = true);
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+		let dropped = RawEvent::Dropped(sibling(), None, ALICE, 50);
+		assert!(events().contains(&TestEvent::deposit_guard(dropped)));
+
+		// The quarantine of another origin is not affected.
+		assert!(!Dealer::receive_native(other_sibling(), &ALICE, 50));
+		assert_eq!(DepositGuard::quarantined_count(other_sibling()), 1);
+	});
+}
+
+#[test]
+fn full_quarantine_keeps_transfers_while_outbound_is_paused() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_allowlist(RawOrigin::Root.into(), None, Some(vec![])));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+
+		OUTBOUND_PAUSED.with(|paused| *paused.borrow_mut() = true);
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+
+		assert!(sent().is_empty());
+		assert!(DepositGuard::quarantined(2).is_some());
+		assert_eq!(DepositGuard::quarantined_count(sibling()), 3);
+	});
+}
+
+#[test]
+fn send_back_frees_the_quarantine() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_allowlist(RawOrigin::Root.into(), None, Some(vec![])));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+
+		SEND_FAILS.with(|fails| *fails.borrow_mut() = true);
+		assert_noop!(
+			DepositGuard::send_back(RawOrigin::Root.into(), 0),
+			pallet_asset_dealer::Error::<Test>::SendFailed,
+		);
+
+		SEND_FAILS.with(|fails| *fails.borrow_mut() = false);
+		assert_ok!(DepositGuard::send_back(RawOrigin::Root.into(), 0));
+		assert_eq!(sent(), vec![sibling()]);
+		assert_eq!(DepositGuard::quarantined(0), None);
+		assert_eq!(DepositGuard::quarantined_count(sibling()), 0);
+		assert_noop!(
+			DepositGuard::send_back(RawOrigin::Root.into(), 0),
+			Error::<Test>::UnknownQuarantine,
+		);
+	});
+}
+
+#[test]
+fn release_deposits_despite_the_limits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(DepositGuard::set_allowlist(RawOrigin::Root.into(), None, Some(vec![])));
+		assert!(!Dealer::receive_native(sibling(), &ALICE, 50));
+
+		assert_ok!(DepositGuard::release(RawOrigin::Root.into(), 0));
+		assert_eq!(Balances::free_balance(ALICE), 50);
+		assert_noop!(
+			DepositGuard::release(RawOrigin::Root.into(), 0),
+			Error::<Test>::UnknownQuarantine,
+		);
+	});
+}
@@ -0,0 +1,201 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that opens, accepts and closes the HRMP channels of this parachain.
+//!
+//! Root requests channels through upward messages holding calls of the HRMP pallet of the relay
+//! chain, dispatched there with the origin of this parachain. The relay chain does not report
+//! back, so `Channels` keeps the state of every channel as requested by this parachain until
+//! root forgets it, e.g. once the relay chain closed it.
+//!
+//! The calls are encoded with `RelayHrmpPalletIndex`, the index of the HRMP pallet in the
+//! runtime of the relay chain. It is set at genesis and root can change it, e.g. after a relay
+//! chain upgrade moved the pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use cumulus_primitives::{ParaId, UpwardMessageOrigin, UpwardMessageSender};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure, traits::Get,
+	IterableStorageDoubleMap,
+};
+use frame_system::ensure_root;
+use parachain_primitives::{ChannelState, HrmpChannel};
+use sp_runtime::{DispatchResult, RuntimeDebug};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+/// A call of the HRMP pallet of the relay chain, encoded like it: `hrmp_init_open_channel`,
+/// `hrmp_accept_open_channel` and `hrmp_close_channel`, the first three calls of the pallet.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub enum HrmpCall {
+	/// Request a channel to the recipient, with the most messages it may hold and the largest
+	/// message it may carry.
+	#[codec(index = "0")]
+	InitOpenChannel(ParaId, u32, u32),
+	/// Accept the request of the sender to open a channel.
+	#[codec(index = "1")]
+	AcceptOpenChannel(ParaId),
+	/// Close the channel from the sender to the recipient.
+	#[codec(index = "2")]
+	CloseChannel(ParaId, ParaId),
+}
+
+/// A call of the relay chain: the index of its HRMP pallet, followed by the call.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub struct RelayHrmpCall(pub u8, pub HrmpCall);
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The id of this parachain.
+	type SelfParaId: Get<ParaId>;
+
+	/// The upward message type.
+	type UpwardMessage: From<RelayHrmpCall>;
+
+	/// Sends the upward messages.
+	type UpwardMessageSender: UpwardMessageSender<Self::UpwardMessage>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as HrmpChannels {
+		/// The channels of this parachain by sender and recipient.
+		Channels get(fn channel): double_map hasher(twox_64_concat) ParaId,
+			hasher(twox_64_concat) ParaId => Option<ChannelState>;
+
+		/// Index of the HRMP pallet in the runtime of the relay chain.
+		RelayHrmpPalletIndex get(fn relay_hrmp_pallet_index) config(): u8;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// A channel to a parachain was requested: the most messages it may hold and the largest
+		/// message it may carry.
+		OpenRequested(ParaId, u32, u32),
+		/// The channel request of a parachain was accepted.
+		Accepted(ParaId),
+		/// Closing the channel from a sender to a recipient was requested.
+		CloseRequested(ParaId, ParaId),
+		/// The channel from a sender to a recipient was forgotten.
+		Forgotten(ParaId, ParaId),
+		/// The index of the HRMP pallet in the runtime of the relay chain was set.
+		RelayHrmpPalletIndexSet(u8),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// A channel cannot connect this parachain to itself.
+		OwnChannel,
+		/// This parachain neither sends nor receives through the channel.
+		NotParticipant,
+		/// The channel was already requested or accepted.
+		ChannelExists,
+		/// The channel is unknown.
+		UnknownChannel,
+		/// Closing the channel was already requested.
+		AlreadyClosing,
+		/// The upward message could not be sent.
+		SendFailed,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Request a channel to `recipient`. It opens once `recipient` accepts it.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+		pub fn open_channel(
+			origin,
+			recipient: ParaId,
+			max_capacity: u32,
+			max_message_size: u32,
+		) {
+			ensure_root(origin)?;
+			let sender = T::SelfParaId::get();
+			ensure!(recipient != sender, Error::<T>::OwnChannel);
+			ensure!(!Channels::contains_key(sender, recipient), Error::<T>::ChannelExists);
+
+			Self::send(HrmpCall::InitOpenChannel(recipient, max_capacity, max_message_size))?;
+
+			let state = ChannelState::OpenRequested { max_capacity, max_message_size };
+			Channels::insert(sender, recipient, state);
+			Self::deposit_event(Event::OpenRequested(recipient, max_capacity, max_message_size));
+		}
+
+		/// Accept the request of `sender` to open a channel to this parachain.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+		pub fn accept_channel(origin, sender: ParaId) {
+			ensure_root(origin)?;
+			let recipient = T::SelfParaId::get();
+			ensure!(sender != recipient, Error::<T>::OwnChannel);
+			ensure!(!Channels::contains_key(sender, recipient), Error::<T>::ChannelExists);
+
+			Self::send(HrmpCall::AcceptOpenChannel(sender))?;
+
+			Channels::insert(sender, recipient, ChannelState::Accepted);
+			Self::deposit_event(Event::Accepted(sender));
+		}
+
+		/// Request to close the channel from `sender` to `recipient`, one of which must be this
+		/// parachain. The channel must have been requested or accepted by this parachain.
+		#[weight = 50_000_000 + T::DbWeight::get().reads_writes(2, 1)]
+		pub fn close_channel(origin, sender: ParaId, recipient: ParaId) {
+			ensure_root(origin)?;
+			let para_id = T::SelfParaId::get();
+			ensure!(sender == para_id || recipient == para_id, Error::<T>::NotParticipant);
+			let state = Channels::get(sender, recipient).ok_or(Error::<T>::UnknownChannel)?;
+			ensure!(state != ChannelState::CloseRequested, Error::<T>::AlreadyClosing);
+
+			Self::send(HrmpCall::CloseChannel(sender, recipient))?;
+
+			Channels::insert(sender, recipient, ChannelState::CloseRequested);
+			Self::deposit_event(Event::CloseRequested(sender, recipient));
+		}
+
+		/// Forget the channel from `sender` to `recipient`, e.g. once the relay chain closed it
+		/// or rejected a request.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+		pub fn forget_channel(origin, sender: ParaId, recipient: ParaId) {
+			ensure_root(origin)?;
+			ensure!(Channels::contains_key(sender, recipient), Error::<T>::UnknownChannel);
+
+			Channels::remove(sender, recipient);
+			Self::deposit_event(Event::Forgotten(sender, recipient));
+		}
+
+		/// Set the index of the HRMP pallet in the runtime of the relay chain.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_relay_hrmp_pallet_index(origin, index: u8) {
+			ensure_root(origin)?;
+
+			RelayHrmpPalletIndex::put(index);
+			Self::deposit_event(Event::RelayHrmpPalletIndexSet(index));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The channels of this parachain.
+	pub fn channels() -> Vec<HrmpChannel> {
+		Channels::iter()
+			.map(|(sender, recipient, state)| HrmpChannel { sender, recipient, state })
+			.collect()
+	}
+
+	/// Send `call` to the HRMP pallet of the relay chain.
+	fn send(call: HrmpCall) -> DispatchResult {
+		let msg = T::UpwardMessage::from(RelayHrmpCall(Self::relay_hrmp_pallet_index(), call));
+		T::UpwardMessageSender::send_upward_message(&msg, UpwardMessageOrigin::Parachain)
+			.map_err(|_| Error::<T>::SendFailed.into())
+	}
+}
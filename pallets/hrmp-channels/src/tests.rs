@@ -0,0 +1,246 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the channel requests, their state and the encoding of their upward messages.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+	weights::Weight,
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+use std::cell::RefCell;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod hrmp_channels {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		hrmp_channels,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+thread_local! {
+	static SENT: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+	static SEND_FAILS: RefCell<bool> = RefCell::new(false);
+}
+
+/// Records the encoding of the messages it sends, or fails while `SEND_FAILS` is set.
+pub struct TestSender;
+
+impl UpwardMessageSender<RelayHrmpCall> for TestSender {
+	fn send_upward_message(msg: &RelayHrmpCall, _origin: UpwardMessageOrigin) -> Result<(), ()> {
+		if SEND_FAILS.with(|fails| *fails.borrow()) {
+			return Err(());
+		}
+		SENT.with(|sent| sent.borrow_mut().push(msg.encode()));
+		Ok(())
+	}
+}
+
+/// This parachain, 100.
+pub struct SelfParaId;
+
+impl Get<ParaId> for SelfParaId {
+	fn get() -> ParaId {
+		ParaId::from(100)
+	}
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type SelfParaId = SelfParaId;
+	type UpwardMessage = RelayHrmpCall;
+	type UpwardMessageSender = TestSender;
+}
+
+type System = frame_system::Module<Test>;
+type HrmpChannels = Module<Test>;
+
+const SELF: u32 = 100;
+const SIBLING: u32 = 200;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	SENT.with(|sent| sent.borrow_mut().clear());
+	SEND_FAILS.with(|fails| *fails.borrow_mut() = false);
+
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisConfig { relay_hrmp_pallet_index: 18 }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn para(id: u32) -> ParaId {
+	ParaId::from(id)
+}
+
+fn sent() -> Vec<Vec<u8>> {
+	SENT.with(|sent| sent.borrow_mut().drain(..).collect())
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn calls_are_encoded_like_the_hrmp_pallet_of_the_relay_chain() {
+	// `hrmp_init_open_channel(recipient, proposed_max_capacity, proposed_max_message_size)`.
+	assert_eq!(
+		RelayHrmpCall(18, HrmpCall::InitOpenChannel(para(200), 8, 1024)).encode(),
+		vec![18, 0, 200, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0],
+	);
+	// `hrmp_accept_open_channel(sender)`.
+	assert_eq!(
+		RelayHrmpCall(18, HrmpCall::AcceptOpenChannel(para(200))).encode(),
+		vec![18, 1, 200, 0, 0, 0],
+	);
+	// `hrmp_close_channel(HrmpChannelId { sender, recipient })`.
+	assert_eq!(
+		RelayHrmpCall(18, HrmpCall::CloseChannel(para(100), para(300))).encode(),
+		vec![18, 2, 100, 0, 0, 0, 44, 1, 0, 0],
+	);
+}
+
+#[test]
+fn open_channel_sends_the_request_and_records_it() {
+	new_test_ext().execute_with(|| {
+		let recipient = para(SIBLING);
+		assert_noop!(HrmpChannels::open_channel(Origin::signed(1), recipient, 8, 1024), BadOrigin);
+		assert_noop!(
+			HrmpChannels::open_channel(RawOrigin::Root.into(), para(SELF), 8, 1024),
+			Error::<Test>::OwnChannel,
+		);
+
+		assert_ok!(HrmpChannels::open_channel(RawOrigin::Root.into(), recipient, 8, 1024));
+
+		assert_eq!(sent(), vec![vec![18, 0, 200, 0, 0, 0, 8, 0, 0, 0, 0, 4, 0, 0]]);
+		assert_eq!(
+			HrmpChannels::channel(para(SELF), recipient),
+			Some(ChannelState::OpenRequested { max_capacity: 8, max_message_size: 1024 }),
+		);
+		assert_eq!(
+			last_event(),
+			TestEvent::hrmp_channels(Event::OpenRequested(recipient, 8, 1024)),
+		);
+		assert_noop!(
+			HrmpChannels::open_channel(RawOrigin::Root.into(), recipient, 8, 1024),
+			Error::<Test>::ChannelExists,
+		);
+	});
+}
+
+#[test]
+fn accept_channel_uses_the_current_pallet_index() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(HrmpChannels::set_relay_hrmp_pallet_index(RawOrigin::Root.into(), 22));
+
+		assert_ok!(HrmpChannels::accept_channel(RawOrigin::Root.into(), para(SIBLING)));
+
+		assert_eq!(sent(), vec![vec![22, 1, 200, 0, 0, 0]]);
+		assert_eq!(HrmpChannels::channel(para(SIBLING), para(SELF)), Some(ChannelState::Accepted));
+		assert_noop!(
+			HrmpChannels::accept_channel(RawOrigin::Root.into(), para(SIBLING)),
+			Error::<Test>::ChannelExists,
+		);
+	});
+}
+
+#[test]
+fn close_channel_requires_a_recorded_channel_not_yet_closing() {
+	new_test_ext().execute_with(|| {
+		let (sender, recipient) = (para(SELF), para(SIBLING));
+		assert_noop!(
+			HrmpChannels::close_channel(RawOrigin::Root.into(), para(200), para(300)),
+			Error::<Test>::NotParticipant,
+		);
+		assert_noop!(
+			HrmpChannels::close_channel(RawOrigin::Root.into(), sender, recipient),
+			Error::<Test>::UnknownChannel,
+		);
+		assert_ok!(HrmpChannels::open_channel(RawOrigin::Root.into(), recipient, 8, 1024));
+		sent();
+
+		assert_ok!(HrmpChannels::close_channel(RawOrigin::Root.into(), sender, recipient));
+
+		assert_eq!(sent(), vec![vec![18, 2, 100, 0, 0, 0, 200, 0, 0, 0]]);
+		assert_eq!(HrmpChannels::channel(sender, recipient), Some(ChannelState::CloseRequested));
+		assert_noop!(
+			HrmpChannels::close_channel(RawOrigin::Root.into(), sender, recipient),
+			Error::<Test>::AlreadyClosing,
+		);
+
+		assert_ok!(HrmpChannels::forget_channel(RawOrigin::Root.into(), sender, recipient));
+		assert!(HrmpChannels::channels().is_empty());
+		assert_noop!(
+			HrmpChannels::forget_channel(RawOrigin::Root.into(), sender, recipient),
+			Error::<Test>::UnknownChannel,
+		);
+	});
+}
+
+#[test]
+fn nothing_is_recorded_when_sending_fails() {
+	new_test_ext().execute_with(|| {
+		SEND_FAILS.with(|fails| *fails.borrow_mut() = true);
+
+		assert_noop!(
+			HrmpChannels::open_channel(RawOrigin::Root.into(), para(SIBLING), 8, 1024),
+			Error::<Test>::SendFailed,
+		);
+		assert!(HrmpChannels::channels().is_empty());
+	});
+}
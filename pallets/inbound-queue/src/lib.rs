@@ -0,0 +1,256 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that queues inbound messages and handles them across blocks.
+//!
+//! `Enqueue` takes the downward and XCMP messages of the message broker and stores them in
+//! arrival order. At the start of every block, queued messages are handed to their handlers
+//! while their `WeightPerMessage` fits into `MaxServiceWeight`, and the rest is carried over to
+//! the next block. A block that handles messages reports the number handled and the number left
+//! with a `Processed` event. Nothing is handled while `Paused` is `true`.
+//!
+//! At most `MaxQueuedPerOrigin` messages of the relay chain or of a sibling parachain are in the
+//! queue at a time, so one sender cannot crowd out the others. Further messages of that sender
+//! wait in its overflow queue, with an `Overflowed` event, and join the queue in arrival order
+//! as its messages in the queue are handled. No message is dropped.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, FullCodec};
+use cumulus_primitives::{
+	xcmp::XCMPMessageHandler, DownwardMessage, DownwardMessageHandler, ParaId,
+};
+use frame_support::{decl_event, decl_module, decl_storage, traits::Get, weights::Weight};
+use frame_system::ensure_root;
+use parachain_primitives::MessageOrigin;
+use sp_runtime::RuntimeDebug;
+use sp_std::marker::PhantomData;
+
+#[cfg(test)]
+mod tests;
+
+/// A queued message.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub enum InboundMessage<XCMPMessage> {
+	/// A downward message from the relay chain.
+	Downward(DownwardMessage),
+	/// An XCMP message from a sibling parachain.
+	Xcmp(ParaId, XCMPMessage),
+}
+
+impl<XCMPMessage> InboundMessage<XCMPMessage> {
+	/// Where the message comes from.
+	pub fn origin(&self) -> MessageOrigin {
+		match self {
+			InboundMessage::Downward(_) => MessageOrigin::Relay,
+			InboundMessage::Xcmp(src, _) => MessageOrigin::Parachain(*src),
+		}
+	}
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The XCMP message type.
+	type XCMPMessage: FullCodec + Clone;
+
+	/// Handle the queued downward messages.
+	type DownwardMessageHandlers: DownwardMessageHandler;
+
+	/// Handle the queued XCMP messages.
+	type XCMPMessageHandlers: XCMPMessageHandler<Self::XCMPMessage>;
+
+	/// Weight of handling a message.
+	type WeightPerMessage: Get<Weight>;
+
+	/// Whether handling messages is paused. Messages keep being queued while it is.
+	type Paused: Get<bool>;
+
+	/// Maximum number of queued messages of a single origin.
+	type MaxQueuedPerOrigin: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as InboundQueue {
+		/// The queued messages by position.
+		Queue: map hasher(twox_64_concat) u64 => Option<InboundMessage<T::XCMPMessage>>;
+
+		/// Position of the oldest message in `Queue`.
+		QueueHead: u64;
+
+		/// Position of the next message in `Queue`.
+		QueueTail: u64;
+
+		/// Number of messages in `Queue` by origin.
+		QueuedPerOrigin get(fn queued):
+			map hasher(twox_64_concat) MessageOrigin => u32;
+
+		/// The messages of every origin waiting for room in `Queue`, by position.
+		Overflow: double_map hasher(twox_64_concat) MessageOrigin,
+			hasher(twox_64_concat) u64 => Option<InboundMessage<T::XCMPMessage>>;
+
+		/// Positions of the oldest and of the next message in `Overflow` of every origin.
+		OverflowBounds: map hasher(twox_64_concat) MessageOrigin => (u64, u64);
+
+		/// Number of messages in `Overflow`.
+		OverflowCount get(fn overflow_count): u64;
+
+		/// Maximum weight of the messages handled in a block.
+		MaxServiceWeight get(fn max_service_weight) config(): Weight;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// Queued messages were handled: the number handled and the number left.
+		Processed(u32, u64),
+		/// The maximum weight of the messages handled in a block was set.
+		MaxServiceWeightSet(Weight),
+		/// A message waits in the overflow queue of its origin, because `MaxQueuedPerOrigin`
+		/// messages of the origin are queued.
+		Overflowed(MessageOrigin),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Weight of handling a message.
+		const WeightPerMessage: Weight = T::WeightPerMessage::get();
+
+		/// Maximum number of queued messages of a single origin.
+		const MaxQueuedPerOrigin: u32 = T::MaxQueuedPerOrigin::get();
+
+		fn on_initialize() -> Weight {
+			let processed = Self::process() as Weight;
+			// Every handled message is read and removed, and the count of its origin updated. The
+			// oldest message in the overflow queue of its origin, if any, takes its place.
+			let db_weight = T::DbWeight::get().reads_writes(3 + 4 * processed, 3 + 5 * processed);
+			processed.saturating_mul(T::WeightPerMessage::get()).saturating_add(db_weight)
+		}
+
+		/// Set the maximum weight of the messages handled in a block. Messages stay queued while
+		/// it is below `WeightPerMessage`.
+		#[weight = 10_000_000 + T::DbWeight::get().writes(1)]
+		pub fn set_max_service_weight(origin, max_service_weight: Weight) {
+			ensure_root(origin)?;
+
+			MaxServiceWeight::put(max_service_weight);
+			Self::deposit_event(Event::MaxServiceWeightSet(max_service_weight));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The number of queued messages, including the ones in the overflow queues.
+	pub fn queue_depth() -> u64 {
+		QueueTail::get().wrapping_sub(QueueHead::get()).saturating_add(Self::overflow_count())
+	}
+
+	fn enqueue(message: InboundMessage<T::XCMPMessage>) {
+		let origin = message.origin();
+		let queued = Self::queued(origin);
+		if queued >= T::MaxQueuedPerOrigin::get() || OverflowBounds::contains_key(origin) {
+			Self::overflow(origin, message);
+			return;
+		}
+		QueuedPerOrigin::insert(origin, queued + 1);
+		Self::push(message);
+	}
+
+	fn push(message: InboundMessage<T::XCMPMessage>) {
+		let tail = QueueTail::get();
+		<Queue<T>>::insert(tail, message);
+		QueueTail::put(tail.wrapping_add(1));
+	}
+
+	fn overflow(origin: MessageOrigin, message: InboundMessage<T::XCMPMessage>) {
+		let (head, tail) = OverflowBounds::get(origin);
+		<Overflow<T>>::insert(origin, tail, message);
+		OverflowBounds::insert(origin, (head, tail.wrapping_add(1)));
+		OverflowCount::mutate(|count| *count = count.saturating_add(1));
+		Self::deposit_event(Event::Overflowed(origin));
+	}
+
+	/// Move the oldest message in the overflow queue of `origin`, if any, to `Queue`.
+	fn promote(origin: MessageOrigin) {
+		if !OverflowBounds::contains_key(origin) {
+			return;
+		}
+		let (head, tail) = OverflowBounds::get(origin);
+
+		if let Some(message) = <Overflow<T>>::take(origin, head) {
+			QueuedPerOrigin::mutate(origin, |queued| *queued += 1);
+			OverflowCount::mutate(|count| *count = count.saturating_sub(1));
+			Self::push(message);
+		}
+
+		let head = head.wrapping_add(1);
+		if head == tail {
+			OverflowBounds::remove(origin);
+		} else {
+			OverflowBounds::insert(origin, (head, tail));
+		}
+	}
+
+	/// Handle the oldest messages that fit into `MaxServiceWeight`. Returns the number handled.
+	fn process() -> u32 {
+		if T::Paused::get() {
+			return 0;
+		}
+
+		let (mut head, tail) = (QueueHead::get(), QueueTail::get());
+		let max_weight = Self::max_service_weight();
+		let message_weight = T::WeightPerMessage::get();
+		let (mut processed, mut used): (u32, Weight) = (0, 0);
+
+		while head != tail && used.saturating_add(message_weight) <= max_weight {
+			if let Some(message) = <Queue<T>>::take(head) {
+				let origin = message.origin();
+				Self::dequeued(origin);
+				Self::promote(origin);
+				match message {
+					InboundMessage::Downward(msg) => {
+						T::DownwardMessageHandlers::handle_downward_message(&msg)
+					}
+					InboundMessage::Xcmp(src, msg) => {
+						T::XCMPMessageHandlers::handle_xcmp_message(src, &msg)
+					}
+				}
+			}
+			head = head.wrapping_add(1);
+			processed += 1;
+			used = used.saturating_add(message_weight);
+		}
+
+		if processed > 0 {
+			QueueHead::put(head);
+			Self::deposit_event(Event::Processed(processed, Self::queue_depth()));
+		}
+		processed
+	}
+
+	fn dequeued(origin: MessageOrigin) {
+		match Self::queued(origin) {
+			0 | 1 => QueuedPerOrigin::remove(origin),
+			queued => QueuedPerOrigin::insert(origin, queued - 1),
+		}
+	}
+}
+
+/// Message handler that queues every message.
+pub struct Enqueue<T>(PhantomData<T>);
+
+impl<T: Trait> DownwardMessageHandler for Enqueue<T> {
+	fn handle_downward_message(msg: &DownwardMessage) {
+		Module::<T>::enqueue(InboundMessage::Downward(msg.clone()));
+	}
+}
+
+impl<T: Trait> XCMPMessageHandler<T::XCMPMessage> for Enqueue<T> {
+	fn handle_xcmp_message(src: ParaId, msg: &T::XCMPMessage) {
+		Module::<T>::enqueue(InboundMessage::Xcmp(src, msg.clone()));
+	}
+}
@@ -0,0 +1,274 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the inbound queue across blocks, its weight limit, pausing and per-origin cap.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+	traits::OnInitialize,
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+use std::cell::RefCell;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod inbound_queue {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		inbound_queue,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+thread_local! {
+	static HANDLED: RefCell<Vec<InboundMessage<Vec<u8>>>> = RefCell::new(Vec::new());
+	static PAUSED: RefCell<bool> = RefCell::new(false);
+}
+
+/// Records the messages it handles.
+pub struct Recorder;
+
+impl XCMPMessageHandler<Vec<u8>> for Recorder {
+	fn handle_xcmp_message(src: ParaId, msg: &Vec<u8>) {
+		HANDLED.with(|handled| handled.borrow_mut().push(InboundMessage::Xcmp(src, msg.clone())));
+	}
+}
+
+impl DownwardMessageHandler for Recorder {
+	fn handle_downward_message(msg: &DownwardMessage) {
+		HANDLED.with(|handled| handled.borrow_mut().push(InboundMessage::Downward(msg.clone())));
+	}
+}
+
+/// `true` while `PAUSED` is set.
+pub struct Paused;
+
+impl Get<bool> for Paused {
+	fn get() -> bool {
+		PAUSED.with(|paused| *paused.borrow())
+	}
+}
+
+parameter_types! {
+	pub const WeightPerMessage: Weight = 10;
+	pub const MaxQueuedPerOrigin: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type XCMPMessage = Vec<u8>;
+	type DownwardMessageHandlers = Recorder;
+	type XCMPMessageHandlers = Recorder;
+	type WeightPerMessage = WeightPerMessage;
+	type Paused = Paused;
+	type MaxQueuedPerOrigin = MaxQueuedPerOrigin;
+}
+
+type System = frame_system::Module<Test>;
+type InboundQueue = Module<Test>;
+type Handler = Enqueue<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	HANDLED.with(|handled| handled.borrow_mut().clear());
+	PAUSED.with(|paused| *paused.borrow_mut() = false);
+
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	GenesisConfig { max_service_weight: 30 }
+		.assimilate_storage::<Test>(&mut storage)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Start the next block. Returns the weight of the queue.
+fn next_block() -> Weight {
+	System::set_block_number(System::block_number() + 1);
+	InboundQueue::on_initialize(System::block_number())
+}
+
+fn handled() -> Vec<InboundMessage<Vec<u8>>> {
+	HANDLED.with(|handled| handled.borrow_mut().drain(..).collect())
+}
+
+fn xcmp(para: u32, msg: u8) -> InboundMessage<Vec<u8>> {
+	InboundMessage::Xcmp(ParaId::from(para), vec![msg])
+}
+
+fn send(para: u32, msg: u8) {
+	Handler::handle_xcmp_message(ParaId::from(para), &vec![msg]);
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn messages_are_queued_until_the_next_block() {
+	new_test_ext().execute_with(|| {
+		let downward = DownwardMessage::ParachainSpecific(vec![1]);
+		Handler::handle_downward_message(&downward);
+		send(100, 2);
+
+		assert!(handled().is_empty());
+		assert_eq!(InboundQueue::queue_depth(), 2);
+
+		next_block();
+
+		assert_eq!(handled(), vec![InboundMessage::Downward(downward), xcmp(100, 2)]);
+		assert_eq!(InboundQueue::queue_depth(), 0);
+		assert_eq!(last_event(), TestEvent::inbound_queue(Event::Processed(2, 0)));
+	});
+}
+
+#[test]
+fn messages_beyond_the_service_weight_are_carried_over() {
+	new_test_ext().execute_with(|| {
+		for para in 100..104 {
+			send(para, 1);
+		}
+
+		assert_eq!(next_block(), 30);
+		assert_eq!(handled(), vec![xcmp(100, 1), xcmp(101, 1), xcmp(102, 1)]);
+		assert_eq!(last_event(), TestEvent::inbound_queue(Event::Processed(3, 1)));
+
+		assert_eq!(next_block(), 10);
+		assert_eq!(handled(), vec![xcmp(103, 1)]);
+
+		assert_eq!(next_block(), 0);
+	});
+}
+
+#[test]
+fn nothing_is_handled_below_the_weight_of_a_message() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(InboundQueue::set_max_service_weight(Origin::signed(1), 5), BadOrigin);
+		assert_ok!(InboundQueue::set_max_service_weight(RawOrigin::Root.into(), 5));
+		send(100, 1);
+
+		assert_eq!(next_block(), 0);
+		assert!(handled().is_empty());
+
+		assert_ok!(InboundQueue::set_max_service_weight(RawOrigin::Root.into(), 10));
+		assert_eq!(next_block(), 10);
+		assert_eq!(handled(), vec![xcmp(100, 1)]);
+	});
+}
+
+#[test]
+fn paused_queue_keeps_queueing() {
+	new_test_ext().execute_with(|| {
+		PAUSED.with(|paused| *paused.borrow_mut() = true);
+		send(100, 1);
+		send(101, 1);
+
+		assert_eq!(next_block(), 0);
+		assert!(handled().is_empty());
+		assert_eq!(InboundQueue::queue_depth(), 2);
+
+		PAUSED.with(|paused| *paused.borrow_mut() = false);
+		next_block();
+		assert_eq!(handled(), vec![xcmp(100, 1), xcmp(101, 1)]);
+	});
+}
+
+#[test]
+fn messages_above_the_cap_of_their_origin_wait_in_order() {
+	new_test_ext().execute_with(|| {
+		for msg in 1..=4 {
+			send(100, msg);
+		}
+		send(200, 1);
+
+		assert_eq!(InboundQueue::queued(MessageOrigin::Parachain(ParaId::from(100))), 2);
+		assert_eq!(InboundQueue::overflow_count(), 2);
+		assert_eq!(InboundQueue::queue_depth(), 5);
+		let overflowed = Event::Overflowed(MessageOrigin::Parachain(ParaId::from(100)));
+		assert_eq!(
+			System::events()
+				.into_iter()
+				.filter(|record| record.event == TestEvent::inbound_queue(overflowed.clone()))
+				.count(),
+			2,
+		);
+
+		// The other origin is not held up by the overflowed messages.
+		next_block();
+		assert_eq!(handled(), vec![xcmp(100, 1), xcmp(100, 2), xcmp(200, 1)]);
+		assert_eq!(InboundQueue::overflow_count(), 0);
+
+		next_block();
+		assert_eq!(handled(), vec![xcmp(100, 3), xcmp(100, 4)]);
+		assert_eq!(InboundQueue::queue_depth(), 0);
+		assert_eq!(InboundQueue::queued(MessageOrigin::Parachain(ParaId::from(100))), 0);
+	});
+}
+
+#[test]
+fn new_messages_queue_behind_the_overflowed_ones() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(InboundQueue::set_max_service_weight(RawOrigin::Root.into(), 10));
+		for msg in 1..=3 {
+			send(100, msg);
+		}
+
+		next_block();
+		send(100, 4);
+		next_block();
+		next_block();
+		next_block();
+
+		assert_eq!(handled(), vec![xcmp(100, 1), xcmp(100, 2), xcmp(100, 3), xcmp(100, 4)]);
+	});
+}
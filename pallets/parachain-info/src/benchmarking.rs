@@ -0,0 +1,21 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Benchmarks of the calls of this pallet.
+
+use super::*;
+use frame_benchmarking::benchmarks;
+use frame_support::StorageValue;
+
+benchmarks! {
+	_ { }
+
+	set_parachain_id {
+		let origin = T::UpdateOrigin::successful_origin();
+		let parachain_id = ParaId::from(2000);
+	}: {
+		Module::<T>::set_parachain_id(origin, parachain_id)?;
+	}
+	verify {
+		assert_eq!(ParachainId::get(), parachain_id);
+	}
+}
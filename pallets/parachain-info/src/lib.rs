@@ -4,12 +4,37 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{decl_module, decl_storage, traits::Get};
+use frame_support::{
+	decl_module, decl_storage,
+	traits::{EnsureOrigin, Get},
+	weights::Weight,
+};
 
 use cumulus_primitives::ParaId;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+/// Weights of the calls of this pallet.
+pub trait WeightInfo {
+	fn set_parachain_id() -> Weight;
+}
+
+/// Weights that are not benchmarked.
+impl WeightInfo for () {
+	fn set_parachain_id() -> Weight {
+		10_000_000
+	}
+}
+
 /// Configuration trait of this pallet.
-pub trait Trait: frame_system::Trait {}
+pub trait Trait: frame_system::Trait {
+	/// Origin that may change the parachain id.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Weights of the calls of this pallet.
+	type WeightInfo: WeightInfo;
+}
 
 impl<T: Trait> Get<ParaId> for Module<T> {
 	fn get() -> ParaId {
@@ -24,5 +49,13 @@ decl_storage! {
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		/// Change the parachain id, e.g. after the parachain was registered under a new id.
+		#[weight = T::WeightInfo::set_parachain_id()]
+		pub fn set_parachain_id(origin, parachain_id: ParaId) {
+			T::UpdateOrigin::ensure_origin(origin)?;
+
+			ParachainId::put(parachain_id);
+		}
+	}
 }
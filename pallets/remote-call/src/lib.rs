@@ -0,0 +1,194 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that sends calls to sibling parachains and dispatches the calls they send.
+//!
+//! `send` wraps an encoded call of the destination parachain in a `Transact` message, with the
+//! most weight it may use and the most its sender pays for it. An inbound `Transact` is
+//! dispatched with the `SiblingParachain` origin of its sender, after its fee, the fee of its
+//! weight, is paid from the sovereign account of the sender. `EnsureSiblingParachain` of
+//! `pallet_chain_origin` lets pallets accept calls from sibling parachains. Every inbound
+//! `Transact` is reported with a `Dispatched` or a `Rejected` event.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, DecodeLimit, Encode};
+use cumulus_primitives::{xcmp::XCMPMessageSender, ParaId};
+use frame_support::{
+	decl_error, decl_event, decl_module, ensure,
+	dispatch::{DispatchResult, Dispatchable, Parameter, PostDispatchInfo},
+	traits::{
+		Currency, EnsureOrigin, ExistenceRequirement, Get, OnUnbalanced, WithdrawReason,
+	},
+	weights::{GetDispatchInfo, Weight, WeightToFeePolynomial},
+};
+use pallet_chain_origin::Origin;
+use sp_runtime::{traits::AccountIdConversion, RuntimeDebug};
+use sp_std::prelude::*;
+
+#[cfg(test)]
+mod tests;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<
+	<T as frame_system::Trait>::AccountId,
+>>::NegativeImbalance;
+
+/// The deepest nesting of calls decoded from a `Transact`.
+const MAX_CALL_DEPTH: u32 = 64;
+
+/// A call sent to a sibling parachain.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub struct Transact<Balance> {
+	/// The call, encoded for the destination parachain.
+	pub call: Vec<u8>,
+	/// The most weight the call may use.
+	pub max_weight: Weight,
+	/// The most the sender pays for dispatching the call.
+	pub max_fee: Balance,
+}
+
+/// Why an inbound `Transact` was not dispatched.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum RejectReason {
+	/// The call could not be decoded.
+	Undecodable,
+	/// The call weighs more than its `max_weight` or `MaxTransactWeight`.
+	Overweight,
+	/// The fee of the call is above its `max_fee`.
+	FeeTooHigh,
+	/// The sovereign account of the sender cannot pay the fee.
+	CannotPayFee,
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The overarching origin type.
+	type Origin: From<Origin>;
+
+	/// The calls that sibling parachains can send.
+	type Call: Parameter
+		+ Dispatchable<Origin = <Self as Trait>::Origin, PostInfo = PostDispatchInfo>
+		+ GetDispatchInfo;
+
+	/// The XCMP message type.
+	type XCMPMessage: From<Transact<BalanceOf<Self>>>;
+
+	/// Sends the XCMP messages.
+	type XCMPMessageSender: XCMPMessageSender<Self::XCMPMessage>;
+
+	/// The currency the fees are paid in.
+	type Currency: Currency<Self::AccountId>;
+
+	/// Converts the weight of a call to its fee.
+	type WeightToFee: WeightToFeePolynomial<Balance = BalanceOf<Self>>;
+
+	/// Receives the fees.
+	type OnFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Origin that may send calls.
+	type SendOrigin: EnsureOrigin<<Self as frame_system::Trait>::Origin>;
+
+	/// The most weight an inbound call may use.
+	type MaxTransactWeight: Get<Weight>;
+}
+
+decl_event!(
+	pub enum Event<T> where Balance = BalanceOf<T> {
+		/// A call was sent to a sibling parachain.
+		Sent(ParaId),
+		/// A call of a sibling parachain was dispatched: its result and the fee paid.
+		Dispatched(ParaId, DispatchResult, Balance),
+		/// A call of a sibling parachain was not dispatched.
+		Rejected(ParaId, RejectReason),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The message could not be sent.
+		SendFailed,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call
+		where origin: <T as frame_system::Trait>::Origin
+	{
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The most weight an inbound call may use.
+		const MaxTransactWeight: Weight = T::MaxTransactWeight::get();
+
+		/// Send `call`, encoded for `dest`, to be dispatched there with the origin of this
+		/// parachain. Its sovereign account on `dest` pays at most `max_fee` for it.
+		#[weight = 50_000_000]
+		pub fn send(
+			origin,
+			dest: ParaId,
+			call: Vec<u8>,
+			max_weight: Weight,
+			max_fee: BalanceOf<T>,
+		) {
+			T::SendOrigin::ensure_origin(origin)?;
+
+			let msg = T::XCMPMessage::from(Transact { call, max_weight, max_fee });
+			T::XCMPMessageSender::send_xcmp_message(dest, &msg)
+				.map_err(|_| Error::<T>::SendFailed)?;
+
+			Self::deposit_event(RawEvent::Sent(dest));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The account paying the fees of the calls sent by a parachain.
+	pub fn sovereign_account(para_id: ParaId) -> T::AccountId {
+		para_id.into_account()
+	}
+
+	/// Dispatch a call sent by `src`.
+	pub fn transact(src: ParaId, transact: &Transact<BalanceOf<T>>) {
+		match Self::do_transact(src, transact) {
+			Ok((result, fee)) => Self::deposit_event(RawEvent::Dispatched(src, result, fee)),
+			Err(reason) => Self::deposit_event(RawEvent::Rejected(src, reason)),
+		}
+	}
+
+	fn do_transact(
+		src: ParaId,
+		transact: &Transact<BalanceOf<T>>,
+	) -> Result<(DispatchResult, BalanceOf<T>), RejectReason> {
+		let call = <T as Trait>::Call::decode_all_with_depth_limit(MAX_CALL_DEPTH, &transact.call)
+			.map_err(|_| RejectReason::Undecodable)?;
+
+		let weight = call.get_dispatch_info().weight;
+		ensure!(
+			weight <= transact.max_weight && weight <= T::MaxTransactWeight::get(),
+			RejectReason::Overweight,
+		);
+
+		let fee = T::WeightToFee::calc(&weight);
+		ensure!(fee <= transact.max_fee, RejectReason::FeeTooHigh);
+
+		let imbalance = T::Currency::withdraw(
+			&Self::sovereign_account(src),
+			fee,
+			WithdrawReason::TransactionPayment.into(),
+			ExistenceRequirement::KeepAlive,
+		)
+		.map_err(|_| RejectReason::CannotPayFee)?;
+		T::OnFee::on_unbalanced(imbalance);
+
+		let result = call
+			.dispatch(Origin::SiblingParachain(src).into())
+			.map(|_| ())
+			.map_err(|e| e.error);
+		Ok((result, fee))
+	}
+}
@@ -0,0 +1,262 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of sending calls, and of the fees and reject reasons of the calls of sibling parachains.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_dispatch, impl_outer_event, impl_outer_origin,
+	parameter_types, weights::IdentityFee,
+};
+use frame_system::{EnsureRoot, RawOrigin};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	DispatchError, Perbill,
+};
+use std::cell::RefCell;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {
+		pallet_chain_origin,
+	}
+}
+
+mod remote_call {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		remote_call<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum OuterCall for Test where origin: Origin {
+		frame_system::System,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = OuterCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 10;
+}
+
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+thread_local! {
+	static SENT: RefCell<Vec<(ParaId, Transact<u64>)>> = RefCell::new(Vec::new());
+	static SEND_FAILS: RefCell<bool> = RefCell::new(false);
+}
+
+/// Records the messages it sends, or fails while `SEND_FAILS` is set.
+pub struct TestSender;
+
+impl XCMPMessageSender<Transact<u64>> for TestSender {
+	fn send_xcmp_message(dest: ParaId, msg: &Transact<u64>) -> Result<(), ()> {
+		if SEND_FAILS.with(|fails| *fails.borrow()) {
+			return Err(());
+		}
+		SENT.with(|sent| sent.borrow_mut().push((dest, msg.clone())));
+		Ok(())
+	}
+}
+
+parameter_types! {
+	pub const MaxTransactWeight: Weight = 800;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type Origin = Origin;
+	type Call = OuterCall;
+	type XCMPMessage = Transact<u64>;
+	type XCMPMessageSender = TestSender;
+	type Currency = Balances;
+	type WeightToFee = IdentityFee<u64>;
+	type OnFee = ();
+	type SendOrigin = EnsureRoot<u64>;
+	type MaxTransactWeight = MaxTransactWeight;
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type RemoteCall = Module<Test>;
+
+const SIBLING: u32 = 200;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	SENT.with(|sent| sent.borrow_mut().clear());
+	SEND_FAILS.with(|fails| *fails.borrow_mut() = false);
+
+	let mut storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(RemoteCall::sovereign_account(ParaId::from(SIBLING)), 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// A call weighing half a block, 512, that only root may dispatch.
+fn half_block() -> OuterCall {
+	OuterCall::System(frame_system::Call::fill_block(Perbill::from_percent(50)))
+}
+
+fn transact(call: Vec<u8>, max_weight: Weight, max_fee: u64) {
+	RemoteCall::transact(ParaId::from(SIBLING), &Transact { call, max_weight, max_fee });
+}
+
+fn sovereign_balance() -> u64 {
+	Balances::free_balance(RemoteCall::sovereign_account(ParaId::from(SIBLING)))
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+fn rejected(reason: RejectReason) -> TestEvent {
+	TestEvent::remote_call(RawEvent::Rejected(ParaId::from(SIBLING), reason))
+}
+
+#[test]
+fn send_wraps_the_call_in_a_transact() {
+	new_test_ext().execute_with(|| {
+		let dest = ParaId::from(SIBLING);
+		assert_noop!(RemoteCall::send(Origin::signed(1), dest, vec![1], 100, 50), BadOrigin);
+
+		assert_ok!(RemoteCall::send(RawOrigin::Root.into(), dest, vec![1], 100, 50));
+
+		let sent = Transact { call: vec![1], max_weight: 100, max_fee: 50 };
+		assert_eq!(SENT.with(|sent| sent.borrow().clone()), vec![(dest, sent)]);
+		assert_eq!(last_event(), TestEvent::remote_call(RawEvent::Sent(dest)));
+
+		SEND_FAILS.with(|fails| *fails.borrow_mut() = true);
+		assert_noop!(
+			RemoteCall::send(RawOrigin::Root.into(), dest, vec![1], 100, 50),
+			Error::<Test>::SendFailed,
+		);
+	});
+}
+
+#[test]
+fn transact_pays_the_fee_from_the_sovereign_account_and_dispatches() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(half_block().get_dispatch_info().weight, 512);
+
+		transact(half_block().encode(), 512, 512);
+
+		assert_eq!(sovereign_balance(), 488);
+		// The call is dispatched with the origin of the sibling, not as root.
+		assert_eq!(
+			last_event(),
+			TestEvent::remote_call(RawEvent::Dispatched(
+				ParaId::from(SIBLING),
+				Err(DispatchError::BadOrigin),
+				512,
+			)),
+		);
+	});
+}
+
+#[test]
+fn undecodable_call_is_rejected() {
+	new_test_ext().execute_with(|| {
+		transact(vec![255, 255], 512, 512);
+
+		assert_eq!(last_event(), rejected(RejectReason::Undecodable));
+		assert_eq!(sovereign_balance(), 1_000);
+	});
+}
+
+#[test]
+fn call_above_either_weight_limit_is_rejected() {
+	new_test_ext().execute_with(|| {
+		transact(half_block().encode(), 511, 512);
+		assert_eq!(last_event(), rejected(RejectReason::Overweight));
+
+		let full_block = OuterCall::System(frame_system::Call::fill_block(Perbill::one()));
+		transact(full_block.encode(), 2_000, 2_000);
+		assert_eq!(last_event(), rejected(RejectReason::Overweight));
+
+		assert_eq!(sovereign_balance(), 1_000);
+	});
+}
+
+#[test]
+fn call_with_a_fee_above_its_max_fee_is_rejected() {
+	new_test_ext().execute_with(|| {
+		transact(half_block().encode(), 512, 511);
+
+		assert_eq!(last_event(), rejected(RejectReason::FeeTooHigh));
+		assert_eq!(sovereign_balance(), 1_000);
+	});
+}
+
+#[test]
+fn fee_is_not_paid_below_the_existential_deposit() {
+	new_test_ext().execute_with(|| {
+		let sovereign = RemoteCall::sovereign_account(ParaId::from(SIBLING));
+		Balances::make_free_balance_be(&sovereign, 520);
+
+		transact(half_block().encode(), 512, 512);
+
+		assert_eq!(last_event(), rejected(RejectReason::CannotPayFee));
+		assert_eq!(sovereign_balance(), 520);
+
+		Balances::make_free_balance_be(&sovereign, 522);
+		transact(half_block().encode(), 512, 512);
+		assert_eq!(sovereign_balance(), 10);
+	});
+}
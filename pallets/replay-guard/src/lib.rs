@@ -0,0 +1,354 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that keeps messages from being handled twice.
+//!
+//! XCMP messages sent through `SequencedSender` carry a sequence number, counted per
+//! destination. `Deduplicated` takes the messages of the message broker and hands them to
+//! `DownwardMessageHandlers` and `XCMPMessageHandlers`. A numbered message is handled if its
+//! number is above the last one handled from its sender, otherwise it is a redelivery and is
+//! dropped with a `Replayed` event. Identical messages with different numbers are all handled.
+//!
+//! Downward messages and XCMP messages of parachains that do not number them carry no
+//! identifier, so they are identified by their origin and the hash of their encoding, and are
+//! not handled if seen in the last `ReplayWindow` blocks. At most `MaxPrunedPerBlock` expired
+//! hashes are forgotten per block, oldest first. Identical unnumbered messages from the same
+//! origin within the window cannot be told apart from a redelivery, yet may be genuine. Such a
+//! message is not dropped but held with a `Held` event, until `ReleaseOrigin` hands it to its
+//! handler with `release` or drops it with `discard`. At most `MaxHeld` messages are held,
+//! further duplicates are dropped with a `Dropped` event.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode, FullCodec, Input, Output};
+use cumulus_primitives::{
+	xcmp::{XCMPMessageHandler, XCMPMessageSender},
+	DownwardMessage, DownwardMessageHandler, ParaId,
+};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage,
+	traits::{EnsureOrigin, Get},
+	weights::Weight,
+};
+use sp_runtime::{
+	traits::{Hash, Saturating},
+	RuntimeDebug,
+};
+use sp_std::marker::PhantomData;
+
+pub use parachain_primitives::MessageOrigin;
+
+#[cfg(test)]
+mod tests;
+
+/// An XCMP message and the sequence number its sender gave it, if any.
+///
+/// The number is encoded after the message, so parachains that do not number messages decode
+/// the message and ignore the number, and send messages that decode without one.
+#[derive(Clone, PartialEq, RuntimeDebug)]
+pub struct Sequenced<XCMPMessage> {
+	/// The message.
+	pub message: XCMPMessage,
+	/// The number of the message among the ones its sender sent to the destination.
+	pub sequence: Option<u64>,
+}
+
+impl<XCMPMessage: Encode> Encode for Sequenced<XCMPMessage> {
+	fn encode_to<O: Output>(&self, dest: &mut O) {
+		self.message.encode_to(dest);
+		if let Some(sequence) = self.sequence {
+			sequence.encode_to(dest);
+		}
+	}
+}
+
+impl<XCMPMessage: Decode> Decode for Sequenced<XCMPMessage> {
+	fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let message = XCMPMessage::decode(input)?;
+		let sequence = match input.remaining_len()? {
+			Some(0) | None => None,
+			Some(_) => Some(u64::decode(input)?),
+		};
+		Ok(Sequenced { message, sequence })
+	}
+}
+
+/// A message held as a possible duplicate.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub enum HeldMessage<XCMPMessage> {
+	/// A downward message from the relay chain.
+	Downward(DownwardMessage),
+	/// An XCMP message from a sibling parachain.
+	Xcmp(ParaId, XCMPMessage),
+}
+
+/// Identifier of a held message.
+pub type HeldId = u64;
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The XCMP message type.
+	type XCMPMessage: FullCodec + Clone;
+
+	/// Handle the downward messages that are not duplicates.
+	type DownwardMessageHandlers: DownwardMessageHandler;
+
+	/// Handle the XCMP messages that are not duplicates.
+	type XCMPMessageHandlers: XCMPMessageHandler<Self::XCMPMessage>;
+
+	/// Origin that may release or discard held messages.
+	type ReleaseOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Maximum number of held messages.
+	type MaxHeld: Get<u32>;
+
+	/// Number of blocks an unnumbered message is remembered for.
+	type ReplayWindow: Get<Self::BlockNumber>;
+
+	/// Maximum number of expired messages forgotten in a block.
+	type MaxPrunedPerBlock: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as ReplayGuard {
+		/// The number the next message sent to every parachain gets.
+		OutboundSequence get(fn outbound_sequence): map hasher(twox_64_concat) ParaId => u64;
+
+		/// The number above which the next message from every parachain must be.
+		InboundSequence get(fn inbound_sequence): map hasher(twox_64_concat) ParaId => Option<u64>;
+
+		/// The block until which every handled unnumbered message is remembered, by origin and
+		/// hash.
+		Seen get(fn seen): double_map hasher(twox_64_concat) MessageOrigin,
+			hasher(identity) T::Hash => Option<T::BlockNumber>;
+
+		/// The remembered unnumbered messages in the order they were handled.
+		PruneQueue: map hasher(twox_64_concat) u64 => Option<(MessageOrigin, T::Hash)>;
+
+		/// Position of the oldest message in `PruneQueue`.
+		QueueHead: u64;
+
+		/// Position of the next message in `PruneQueue`.
+		QueueTail: u64;
+
+		/// The messages held as possible duplicates.
+		Held get(fn held): map hasher(twox_64_concat) HeldId => Option<HeldMessage<T::XCMPMessage>>;
+
+		/// Number of held messages.
+		HeldCount get(fn held_count): u32;
+
+		/// The identifier of the next held message.
+		NextHeldId: HeldId;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		Hash = <T as frame_system::Trait>::Hash,
+	{
+		/// A message with the number was handled before from the parachain and was dropped.
+		Replayed(ParaId, u64),
+		/// A message with the hash was seen before from the origin and is held with the id.
+		Held(MessageOrigin, Hash, HeldId),
+		/// A message with the hash was seen before from the origin and was dropped, because the
+		/// most messages are held already.
+		Dropped(MessageOrigin, Hash),
+		/// A held message was handed to its handler.
+		Released(HeldId),
+		/// A held message was dropped.
+		Discarded(HeldId),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// No message is held with the given identifier.
+		UnknownMessage,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Number of blocks an unnumbered message is remembered for.
+		const ReplayWindow: T::BlockNumber = T::ReplayWindow::get();
+
+		/// Maximum number of expired messages forgotten in a block.
+		const MaxPrunedPerBlock: u32 = T::MaxPrunedPerBlock::get();
+
+		/// Maximum number of held messages.
+		const MaxHeld: u32 = T::MaxHeld::get();
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			// The queue positions and the first message that has not expired, if any.
+			let pruned = Self::prune(n) as Weight;
+			T::DbWeight::get().reads_writes(4 + 2 * pruned, 1 + 2 * pruned)
+		}
+
+		/// Hand a held message to its handler, e.g. after checking with its sender that it is not
+		/// a redelivery.
+		#[weight = 100_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn release(origin, id: HeldId) {
+			T::ReleaseOrigin::ensure_origin(origin)?;
+			let message = Self::take_held(id)?;
+
+			match message {
+				HeldMessage::Downward(msg) => {
+					T::DownwardMessageHandlers::handle_downward_message(&msg)
+				}
+				HeldMessage::Xcmp(src, msg) => {
+					T::XCMPMessageHandlers::handle_xcmp_message(src, &msg)
+				}
+			}
+			Self::deposit_event(RawEvent::Released(id));
+		}
+
+		/// Drop a held message.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(2, 2)]
+		pub fn discard(origin, id: HeldId) {
+			T::ReleaseOrigin::ensure_origin(origin)?;
+			Self::take_held(id)?;
+
+			Self::deposit_event(RawEvent::Discarded(id));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Remember the number of a message from `src`. Returns `false` if a message with the same
+	/// or a higher number was handled before.
+	///
+	/// Numbers may be skipped, e.g. for messages lost on the way, but never go back.
+	pub fn note_sequence(src: ParaId, sequence: u64) -> bool {
+		if Self::inbound_sequence(src).map_or(false, |last| sequence <= last) {
+			return false;
+		}
+
+		InboundSequence::insert(src, sequence);
+		true
+	}
+
+	/// Remember a message from `origin`. Returns `false` if it was seen before.
+	pub fn note(origin: MessageOrigin, message: &impl Encode) -> bool {
+		let hash = T::Hashing::hash_of(message);
+		if <Seen<T>>::contains_key(origin, hash) {
+			return false;
+		}
+
+		let now = <frame_system::Module<T>>::block_number();
+		<Seen<T>>::insert(origin, hash, now.saturating_add(T::ReplayWindow::get()));
+
+		let tail = QueueTail::get();
+		<PruneQueue<T>>::insert(tail, (origin, hash));
+		QueueTail::put(tail.wrapping_add(1));
+
+		true
+	}
+
+	/// Hold a message from `origin` that was seen before, or drop it if `MaxHeld` messages are
+	/// held already.
+	fn hold(origin: MessageOrigin, message: HeldMessage<T::XCMPMessage>) {
+		let hash = match &message {
+			HeldMessage::Downward(msg) => T::Hashing::hash_of(msg),
+			HeldMessage::Xcmp(_, msg) => T::Hashing::hash_of(msg),
+		};
+		if Self::held_count() >= T::MaxHeld::get() {
+			Self::deposit_event(RawEvent::Dropped(origin, hash));
+			return;
+		}
+
+		let id = NextHeldId::mutate(|next| {
+			let id = *next;
+			*next = next.wrapping_add(1);
+			id
+		});
+		<Held<T>>::insert(id, message);
+		HeldCount::mutate(|count| *count += 1);
+		Self::deposit_event(RawEvent::Held(origin, hash, id));
+	}
+
+	fn take_held(id: HeldId) -> Result<HeldMessage<T::XCMPMessage>, Error<T>> {
+		let message = <Held<T>>::take(id).ok_or(Error::<T>::UnknownMessage)?;
+		HeldCount::mutate(|count| *count = count.saturating_sub(1));
+		Ok(message)
+	}
+
+	/// Forget up to `MaxPrunedPerBlock` messages that expired by block `n`, oldest first.
+	/// Returns the number of messages forgotten.
+	fn prune(n: T::BlockNumber) -> u32 {
+		let (mut head, tail) = (QueueHead::get(), QueueTail::get());
+		let mut pruned = 0;
+
+		while head != tail && pruned < T::MaxPrunedPerBlock::get() {
+			if let Some((origin, hash)) = <PruneQueue<T>>::get(head) {
+				if Self::seen(origin, hash).map_or(false, |until| until > n) {
+					break;
+				}
+				<Seen<T>>::remove(origin, hash);
+				<PruneQueue<T>>::remove(head);
+			}
+			head = head.wrapping_add(1);
+			pruned += 1;
+		}
+
+		QueueHead::put(head);
+		pruned
+	}
+}
+
+/// Message handler that hands every message to the handlers of `T` unless it is a duplicate,
+/// which is held instead.
+pub struct Deduplicated<T>(PhantomData<T>);
+
+impl<T: Trait> DownwardMessageHandler for Deduplicated<T> {
+	fn handle_downward_message(msg: &DownwardMessage) {
+		if Module::<T>::note(MessageOrigin::Relay, msg) {
+			T::DownwardMessageHandlers::handle_downward_message(msg);
+		} else {
+			Module::<T>::hold(MessageOrigin::Relay, HeldMessage::Downward(msg.clone()));
+		}
+	}
+}
+
+impl<T: Trait> XCMPMessageHandler<Sequenced<T::XCMPMessage>> for Deduplicated<T> {
+	fn handle_xcmp_message(src: ParaId, msg: &Sequenced<T::XCMPMessage>) {
+		let Sequenced { message, sequence } = msg;
+		let origin = MessageOrigin::Parachain(src);
+
+		match sequence {
+			Some(sequence) if !Module::<T>::note_sequence(src, *sequence) => {
+				Module::<T>::deposit_event(RawEvent::Replayed(src, *sequence));
+			}
+			Some(_) => T::XCMPMessageHandlers::handle_xcmp_message(src, message),
+			None if Module::<T>::note(origin, message) => {
+				T::XCMPMessageHandlers::handle_xcmp_message(src, message);
+			}
+			None => Module::<T>::hold(origin, HeldMessage::Xcmp(src, message.clone())),
+		}
+	}
+}
+
+/// XCMP message sender that numbers the messages for their destination and sends them through
+/// `Sender`. A number is only used up if the message is sent.
+pub struct SequencedSender<T, Sender>(PhantomData<(T, Sender)>);
+
+impl<T, Sender> XCMPMessageSender<T::XCMPMessage> for SequencedSender<T, Sender>
+where
+	T: Trait,
+	Sender: XCMPMessageSender<Sequenced<T::XCMPMessage>>,
+{
+	fn send_xcmp_message(dest: ParaId, msg: &T::XCMPMessage) -> Result<(), ()> {
+		let sequence = Module::<T>::outbound_sequence(dest);
+		let msg = Sequenced { message: msg.clone(), sequence: Some(sequence) };
+
+		Sender::send_xcmp_message(dest, &msg)?;
+		OutboundSequence::insert(dest, sequence.wrapping_add(1));
+		Ok(())
+	}
+}
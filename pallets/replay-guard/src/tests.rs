@@ -0,0 +1,420 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of the replay guard with redelivered messages, numbered or not.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+	traits::OnInitialize,
+	weights::Weight,
+};
+use frame_system::RawOrigin;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+use std::cell::RefCell;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod replay_guard {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		replay_guard<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const ReplayWindow: u64 = 10;
+	pub const MaxPrunedPerBlock: u32 = 2;
+	pub const MaxHeld: u32 = 2;
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type XCMPMessage = Vec<u8>;
+	type DownwardMessageHandlers = Recorder;
+	type XCMPMessageHandlers = Recorder;
+	type ReleaseOrigin = frame_system::EnsureRoot<u64>;
+	type MaxHeld = MaxHeld;
+	type ReplayWindow = ReplayWindow;
+	type MaxPrunedPerBlock = MaxPrunedPerBlock;
+}
+
+type System = frame_system::Module<Test>;
+type ReplayGuard = Module<Test>;
+
+thread_local! {
+	static HANDLED: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+	static HANDLED_DOWNWARD: RefCell<Vec<DownwardMessage>> = RefCell::new(Vec::new());
+}
+
+/// Records the messages it handles.
+pub struct Recorder;
+
+impl XCMPMessageHandler<Vec<u8>> for Recorder {
+	fn handle_xcmp_message(src: ParaId, msg: &Vec<u8>) {
+		HANDLED.with(|handled| handled.borrow_mut().push((src, msg.clone())));
+	}
+}
+
+impl DownwardMessageHandler for Recorder {
+	fn handle_downward_message(msg: &DownwardMessage) {
+		HANDLED_DOWNWARD.with(|handled| handled.borrow_mut().push(msg.clone()));
+	}
+}
+
+type Handler = Deduplicated<Test>;
+
+thread_local! {
+	static SENT: RefCell<Vec<(ParaId, Vec<u8>)>> = RefCell::new(Vec::new());
+}
+
+/// Records the messages it sends.
+pub struct TestSender;
+
+impl XCMPMessageSender<Sequenced<Vec<u8>>> for TestSender {
+	fn send_xcmp_message(dest: ParaId, msg: &Sequenced<Vec<u8>>) -> Result<(), ()> {
+		SENT.with(|sent| sent.borrow_mut().push((dest, msg.encode())));
+		Ok(())
+	}
+}
+
+type Sender = SequencedSender<Test, TestSender>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	HANDLED.with(|handled| handled.borrow_mut().clear());
+	HANDLED_DOWNWARD.with(|handled| handled.borrow_mut().clear());
+	SENT.with(|sent| sent.borrow_mut().clear());
+
+	let storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn unnumbered(message: Vec<u8>) -> Sequenced<Vec<u8>> {
+	Sequenced { message, sequence: None }
+}
+
+fn numbered(sequence: u64, message: Vec<u8>) -> Sequenced<Vec<u8>> {
+	Sequenced { message, sequence: Some(sequence) }
+}
+
+fn handled() -> Vec<(ParaId, Vec<u8>)> {
+	HANDLED.with(|handled| handled.borrow().clone())
+}
+
+fn handled_downward() -> Vec<DownwardMessage> {
+	HANDLED_DOWNWARD.with(|handled| handled.borrow().clone())
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		ReplayGuard::on_initialize(System::block_number());
+	}
+}
+
+fn held() -> Vec<(MessageOrigin, H256, HeldId)> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			TestEvent::replay_guard(RawEvent::Held(origin, hash, id)) => Some((origin, hash, id)),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn redelivered_message_is_handled_once() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+
+		assert_eq!(handled(), vec![(para, msg.clone())]);
+		assert_eq!(held(), vec![(MessageOrigin::Parachain(para), BlakeTwo256::hash_of(&msg), 0)]);
+		assert_eq!(ReplayGuard::held(0), Some(HeldMessage::Xcmp(para, msg)));
+	});
+}
+
+#[test]
+fn redelivery_in_a_later_block_is_held() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		run_to_block(5);
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+
+		assert_eq!(handled(), vec![(para, msg)]);
+		assert_eq!(held().len(), 1);
+	});
+}
+
+#[test]
+fn same_message_from_other_origins_is_handled() {
+	new_test_ext().execute_with(|| {
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(ParaId::from(100), &unnumbered(msg.clone()));
+		Handler::handle_xcmp_message(ParaId::from(200), &unnumbered(msg.clone()));
+		assert!(ReplayGuard::note(MessageOrigin::Relay, &msg));
+
+		assert_eq!(
+			handled(),
+			vec![(ParaId::from(100), msg.clone()), (ParaId::from(200), msg)],
+		);
+		assert!(held().is_empty());
+	});
+}
+
+#[test]
+fn redelivered_downward_message_is_held() {
+	new_test_ext().execute_with(|| {
+		let msg = DownwardMessage::ParachainSpecific(b"downward".to_vec());
+
+		Handler::handle_downward_message(&msg);
+		Handler::handle_downward_message(&msg);
+
+		assert_eq!(handled_downward(), vec![msg.clone()]);
+		assert_eq!(held(), vec![(MessageOrigin::Relay, BlakeTwo256::hash_of(&msg), 0)]);
+	});
+}
+
+#[test]
+fn released_message_is_handled() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+		let downward = DownwardMessage::ParachainSpecific(b"downward".to_vec());
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		Handler::handle_downward_message(&downward);
+		Handler::handle_downward_message(&downward);
+
+		assert_ok!(ReplayGuard::release(RawOrigin::Root.into(), 0));
+		assert_ok!(ReplayGuard::release(RawOrigin::Root.into(), 1));
+
+		assert_eq!(handled(), vec![(para, msg.clone()), (para, msg)]);
+		assert_eq!(handled_downward(), vec![downward.clone(), downward]);
+		assert_eq!(ReplayGuard::held(0), None);
+		assert_eq!(ReplayGuard::held_count(), 0);
+		assert_noop!(
+			ReplayGuard::release(RawOrigin::Root.into(), 0),
+			Error::<Test>::UnknownMessage,
+		);
+	});
+}
+
+#[test]
+fn discarded_message_is_not_handled() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+
+		assert_noop!(
+			ReplayGuard::discard(Origin::signed(1), 0),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+		assert_ok!(ReplayGuard::discard(RawOrigin::Root.into(), 0));
+
+		assert_eq!(handled(), vec![(para, msg)]);
+		assert_eq!(ReplayGuard::held(0), None);
+		assert_eq!(ReplayGuard::held_count(), 0);
+	});
+}
+
+#[test]
+fn duplicates_beyond_max_held_are_dropped() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		for _ in 0..4 {
+			Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		}
+
+		assert_eq!(handled(), vec![(para, msg.clone())]);
+		assert_eq!(ReplayGuard::held_count(), 2);
+		let dropped = RawEvent::Dropped(MessageOrigin::Parachain(para), BlakeTwo256::hash_of(&msg));
+		assert!(System::events()
+			.into_iter()
+			.any(|record| record.event == TestEvent::replay_guard(dropped.clone())));
+	});
+}
+
+#[test]
+fn message_is_forgotten_after_the_window() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+		let hash = BlakeTwo256::hash_of(&msg);
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		run_to_block(10);
+		assert_eq!(ReplayGuard::seen(MessageOrigin::Parachain(para), hash), Some(11));
+
+		run_to_block(11);
+		assert_eq!(ReplayGuard::seen(MessageOrigin::Parachain(para), hash), None);
+
+		Handler::handle_xcmp_message(para, &unnumbered(msg.clone()));
+		assert_eq!(handled(), vec![(para, msg.clone()), (para, msg)]);
+	});
+}
+
+#[test]
+fn pruning_is_bounded_per_block() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		for i in 0..5u8 {
+			Handler::handle_xcmp_message(para, &unnumbered(vec![i]));
+		}
+
+		run_to_block(11);
+		assert_eq!(QueueHead::get(), 2);
+		assert!(!ReplayGuard::note(MessageOrigin::Parachain(para), &vec![2u8]));
+
+		run_to_block(13);
+		assert_eq!(QueueHead::get(), QueueTail::get());
+		assert!(ReplayGuard::note(MessageOrigin::Parachain(para), &vec![2u8]));
+	});
+}
+
+#[test]
+fn identical_numbered_transfers_are_both_handled() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(para, &numbered(0, msg.clone()));
+		Handler::handle_xcmp_message(para, &numbered(1, msg.clone()));
+
+		assert_eq!(handled(), vec![(para, msg.clone()), (para, msg)]);
+		assert!(held().is_empty());
+		assert_eq!(ReplayGuard::inbound_sequence(para), Some(1));
+	});
+}
+
+#[test]
+fn redelivered_numbered_message_is_dropped() {
+	new_test_ext().execute_with(|| {
+		let para = ParaId::from(100);
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(para, &numbered(0, msg.clone()));
+		Handler::handle_xcmp_message(para, &numbered(3, msg.clone()));
+		Handler::handle_xcmp_message(para, &numbered(3, msg.clone()));
+		Handler::handle_xcmp_message(para, &numbered(2, msg.clone()));
+
+		assert_eq!(handled(), vec![(para, msg.clone()), (para, msg)]);
+		assert!(held().is_empty());
+		let replayed = |sequence| TestEvent::replay_guard(RawEvent::Replayed(para, sequence));
+		let events = System::events().into_iter().map(|record| record.event).collect::<Vec<_>>();
+		assert_eq!(events, vec![replayed(3), replayed(2)]);
+	});
+}
+
+#[test]
+fn numbers_are_counted_per_sender() {
+	new_test_ext().execute_with(|| {
+		let msg = b"transfer".to_vec();
+
+		Handler::handle_xcmp_message(ParaId::from(100), &numbered(5, msg.clone()));
+		Handler::handle_xcmp_message(ParaId::from(200), &numbered(0, msg.clone()));
+
+		assert_eq!(
+			handled(),
+			vec![(ParaId::from(100), msg.clone()), (ParaId::from(200), msg)],
+		);
+	});
+}
+
+#[test]
+fn sent_messages_are_numbered_per_destination() {
+	new_test_ext().execute_with(|| {
+		let msg = b"transfer".to_vec();
+
+		assert_ok!(Sender::send_xcmp_message(ParaId::from(100), &msg));
+		assert_ok!(Sender::send_xcmp_message(ParaId::from(100), &msg));
+		assert_ok!(Sender::send_xcmp_message(ParaId::from(200), &msg));
+
+		let sent = SENT.with(|sent| sent.borrow().clone());
+		assert_eq!(
+			sent,
+			vec![
+				(ParaId::from(100), numbered(0, msg.clone()).encode()),
+				(ParaId::from(100), numbered(1, msg.clone()).encode()),
+				(ParaId::from(200), numbered(0, msg).encode()),
+			],
+		);
+		assert_eq!(ReplayGuard::outbound_sequence(ParaId::from(100)), 2);
+	});
+}
+
+#[test]
+fn number_is_encoded_after_the_message() {
+	let msg = b"transfer".to_vec();
+	let encoded = numbered(7, msg.clone()).encode();
+
+	assert_eq!(encoded, (msg.clone(), 7u64).encode());
+	assert_eq!(Vec::<u8>::decode(&mut &encoded[..]), Ok(msg.clone()));
+	assert_eq!(Sequenced::decode(&mut &encoded[..]), Ok(numbered(7, msg.clone())));
+	assert_eq!(Sequenced::decode(&mut &msg.encode()[..]), Ok(unnumbered(msg)));
+}
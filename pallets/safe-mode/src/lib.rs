@@ -0,0 +1,176 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Pallet that pauses cross-chain messaging and transfers in an emergency.
+//!
+//! `SafeModeOrigin` pauses a `PauseTarget` for up to `MaxPauseDuration` blocks: outbound
+//! transfers, the handling of inbound messages, or all calls of a pallet by name. A pause ends
+//! with `resume`, or expires on its own at the start of its last block. The runtime enforces the
+//! pauses in its `BaseCallFilter`, which pauses pallets with `PausedPallets`, in the inbound
+//! queue, which keeps queuing messages while their handling is paused, and in the asset dealer,
+//! which sends nothing back while outbound transfers are paused.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{EnsureOrigin, Filter, Get, GetCallMetadata},
+	weights::Weight,
+};
+use sp_runtime::{
+	traits::{Saturating, Zero},
+	RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+#[cfg(test)]
+mod tests;
+
+/// What can be paused.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum PauseTarget {
+	/// Transfers to other chains.
+	Outbound,
+	/// Handling of queued inbound messages.
+	Inbound,
+	/// All calls of the pallet with this name in `construct_runtime`.
+	Pallet(Vec<u8>),
+}
+
+/// Configuration trait of this pallet.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// Origin that can pause and resume.
+	type SafeModeOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The longest a pause can last.
+	type MaxPauseDuration: Get<Self::BlockNumber>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as SafeMode {
+		/// The block at which a pause expires, by target.
+		Paused get(fn paused_until):
+			map hasher(blake2_128_concat) PauseTarget => Option<T::BlockNumber>;
+
+		/// The targets whose pause expires at a block.
+		Expiries: map hasher(twox_64_concat) T::BlockNumber => Vec<PauseTarget>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where BlockNumber = <T as frame_system::Trait>::BlockNumber {
+		/// A target was paused until a block.
+		Paused(PauseTarget, BlockNumber),
+		/// A pause was lifted.
+		Resumed(PauseTarget),
+		/// A pause expired.
+		Expired(PauseTarget),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The duration is zero or above `MaxPauseDuration`.
+		InvalidDuration,
+		/// The target is not paused.
+		NotPaused,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// The longest a pause can last.
+		const MaxPauseDuration: T::BlockNumber = T::MaxPauseDuration::get();
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let targets = Expiries::<T>::take(n);
+			let count = targets.len() as Weight;
+			for target in targets {
+				// The target may have been resumed, or paused again with another expiry.
+				if Self::paused_until(&target) == Some(n) {
+					Paused::<T>::remove(&target);
+					Self::deposit_event(RawEvent::Expired(target));
+				}
+			}
+			T::DbWeight::get().reads_writes(1 + count, 1 + count)
+		}
+
+		/// Pause `target` for `duration` blocks, replacing the expiry of an ongoing pause.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(1, 2)]
+		pub fn pause(origin, target: PauseTarget, duration: T::BlockNumber) {
+			T::SafeModeOrigin::ensure_origin(origin)?;
+			ensure!(
+				!duration.is_zero() && duration <= T::MaxPauseDuration::get(),
+				Error::<T>::InvalidDuration,
+			);
+
+			let until = <frame_system::Module<T>>::block_number().saturating_add(duration);
+			Paused::<T>::insert(&target, until);
+			Expiries::<T>::mutate(until, |targets| targets.push(target.clone()));
+			Self::deposit_event(RawEvent::Paused(target, until));
+		}
+
+		/// Lift the pause of `target` before it expires.
+		#[weight = 10_000_000 + T::DbWeight::get().reads_writes(1, 1)]
+		pub fn resume(origin, target: PauseTarget) {
+			T::SafeModeOrigin::ensure_origin(origin)?;
+			ensure!(Paused::<T>::contains_key(&target), Error::<T>::NotPaused);
+
+			Paused::<T>::remove(&target);
+			Self::deposit_event(RawEvent::Resumed(target));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether `target` is paused.
+	pub fn is_paused(target: &PauseTarget) -> bool {
+		Paused::<T>::contains_key(target)
+	}
+
+	/// Whether the calls of the pallet named `name` are paused.
+	pub fn is_pallet_paused(name: &str) -> bool {
+		Self::is_paused(&PauseTarget::Pallet(name.as_bytes().to_vec()))
+	}
+}
+
+/// `true` while the handling of inbound messages is paused.
+pub struct InboundPaused<T>(PhantomData<T>);
+
+impl<T: Trait> Get<bool> for InboundPaused<T> {
+	fn get() -> bool {
+		Module::<T>::is_paused(&PauseTarget::Inbound)
+	}
+}
+
+/// `true` while transfers to other chains are paused.
+pub struct OutboundPaused<T>(PhantomData<T>);
+
+impl<T: Trait> Get<bool> for OutboundPaused<T> {
+	fn get() -> bool {
+		Module::<T>::is_paused(&PauseTarget::Outbound)
+	}
+}
+
+/// Lets the calls of a pallet through unless it is paused. The pallets named in `Exempt` are
+/// never paused, so that blocks keep being produced and safe mode can always be lifted.
+pub struct PausedPallets<T, Exempt>(PhantomData<(T, Exempt)>);
+
+impl<T, Exempt, Call> Filter<Call> for PausedPallets<T, Exempt>
+where
+	T: Trait,
+	Exempt: Get<&'static [&'static str]>,
+	Call: GetCallMetadata,
+{
+	fn filter(call: &Call) -> bool {
+		let pallet = call.get_call_metadata().pallet_name;
+		Exempt::get().contains(&pallet) || !Module::<T>::is_pallet_paused(pallet)
+	}
+}
@@ -0,0 +1,224 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Tests of pausing and resuming, the expiry of pauses and the filtering of paused pallets.
+
+use super::*;
+use frame_support::{
+	assert_noop, assert_ok, impl_outer_dispatch, impl_outer_event, impl_outer_origin,
+	parameter_types, traits::OnInitialize,
+};
+use frame_system::{EnsureRoot, RawOrigin};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+	Perbill,
+};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod safe_mode {
+	pub use crate::{Call, Event};
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		safe_mode<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum OuterCall for Test where origin: Origin {
+		frame_system::System,
+		safe_mode::SafeMode,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Call = OuterCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+
+parameter_types! {
+	pub const MaxPauseDuration: u64 = 10;
+	pub const Exempt: &'static [&'static str] = &["SafeMode"];
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type SafeModeOrigin = EnsureRoot<u64>;
+	type MaxPauseDuration = MaxPauseDuration;
+}
+
+type System = frame_system::Module<Test>;
+type SafeMode = Module<Test>;
+type Filtered = PausedPallets<Test, Exempt>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Start the blocks up to `n`.
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		SafeMode::on_initialize(System::block_number());
+	}
+}
+
+fn pause(target: PauseTarget, duration: u64) {
+	assert_ok!(SafeMode::pause(RawOrigin::Root.into(), target, duration));
+}
+
+fn events() -> Vec<TestEvent> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn pause_requires_the_origin_and_a_valid_duration() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(SafeMode::pause(Origin::signed(1), PauseTarget::Outbound, 3), BadOrigin);
+		assert_noop!(
+			SafeMode::pause(RawOrigin::Root.into(), PauseTarget::Outbound, 0),
+			Error::<Test>::InvalidDuration,
+		);
+		assert_noop!(
+			SafeMode::pause(RawOrigin::Root.into(), PauseTarget::Outbound, 11),
+			Error::<Test>::InvalidDuration,
+		);
+
+		pause(PauseTarget::Outbound, 10);
+
+		assert!(OutboundPaused::<Test>::get());
+		assert!(!InboundPaused::<Test>::get());
+		assert_eq!(SafeMode::paused_until(PauseTarget::Outbound), Some(11));
+		assert_eq!(
+			last_event(),
+			TestEvent::safe_mode(RawEvent::Paused(PauseTarget::Outbound, 11)),
+		);
+	});
+}
+
+#[test]
+fn pause_expires_at_the_start_of_its_last_block() {
+	new_test_ext().execute_with(|| {
+		pause(PauseTarget::Inbound, 3);
+
+		run_to_block(3);
+		assert!(InboundPaused::<Test>::get());
+
+		run_to_block(4);
+		assert!(!InboundPaused::<Test>::get());
+		assert_eq!(last_event(), TestEvent::safe_mode(RawEvent::Expired(PauseTarget::Inbound)));
+	});
+}
+
+#[test]
+fn pausing_again_replaces_the_expiry() {
+	new_test_ext().execute_with(|| {
+		pause(PauseTarget::Outbound, 2);
+		pause(PauseTarget::Outbound, 5);
+
+		// The first expiry does not end the longer pause.
+		run_to_block(3);
+		assert!(OutboundPaused::<Test>::get());
+
+		run_to_block(6);
+		assert!(!OutboundPaused::<Test>::get());
+
+		pause(PauseTarget::Outbound, 5);
+		pause(PauseTarget::Outbound, 1);
+
+		run_to_block(7);
+		assert!(!OutboundPaused::<Test>::get());
+
+		// Nor does the replaced expiry report the shorter pause again.
+		run_to_block(11);
+		let expired = TestEvent::safe_mode(RawEvent::Expired(PauseTarget::Outbound));
+		assert_eq!(events().into_iter().filter(|event| *event == expired).count(), 2);
+	});
+}
+
+#[test]
+fn resume_lifts_a_pause_before_it_expires() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			SafeMode::resume(RawOrigin::Root.into(), PauseTarget::Inbound),
+			Error::<Test>::NotPaused,
+		);
+		pause(PauseTarget::Inbound, 3);
+
+		assert_noop!(SafeMode::resume(Origin::signed(1), PauseTarget::Inbound), BadOrigin);
+		assert_ok!(SafeMode::resume(RawOrigin::Root.into(), PauseTarget::Inbound));
+
+		assert!(!InboundPaused::<Test>::get());
+		assert_eq!(last_event(), TestEvent::safe_mode(RawEvent::Resumed(PauseTarget::Inbound)));
+
+		run_to_block(4);
+		assert_eq!(last_event(), TestEvent::safe_mode(RawEvent::Resumed(PauseTarget::Inbound)));
+	});
+}
+
+#[test]
+fn calls_of_paused_pallets_are_filtered_unless_exempt() {
+	new_test_ext().execute_with(|| {
+		let remark = OuterCall::System(frame_system::Call::remark(vec![]));
+		let resume = OuterCall::SafeMode(Call::resume(PauseTarget::Inbound));
+		assert!(Filtered::filter(&remark));
+
+		pause(PauseTarget::Pallet(b"System".to_vec()), 3);
+		pause(PauseTarget::Pallet(b"SafeMode".to_vec()), 3);
+
+		assert!(SafeMode::is_pallet_paused("System"));
+		assert!(!Filtered::filter(&remark));
+		assert!(Filtered::filter(&resume));
+
+		run_to_block(4);
+		assert!(Filtered::filter(&remark));
+	});
+}
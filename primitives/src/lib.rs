@@ -0,0 +1,189 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Primitives shared by the pallets of the parachain runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode, FullCodec};
+use cumulus_primitives::ParaId;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, MaybeSerializeDeserialize},
+	ConsensusEngineId, DispatchResult, FixedU128, RuntimeDebug,
+};
+use sp_std::{fmt::Debug, prelude::*};
+
+/// Engine id of the pre-runtime digest in which the author of a block signs its Aura slot.
+///
+/// The digest holds the signature of the slot owner over `(parent_hash, slot)`, SCALE encoded.
+pub const AUTHOR_ENGINE_ID: ConsensusEngineId = *b"auth";
+
+/// Access to the balances of the assets in `pallet_assets`.
+pub trait MultiAsset<AccountId> {
+	/// Identifier of an asset.
+	type AssetId: FullCodec + Copy + Eq + MaybeSerializeDeserialize + Debug;
+
+	/// Balance of an asset.
+	type Balance: AtLeast32BitUnsigned + FullCodec + Copy + MaybeSerializeDeserialize + Debug + Default;
+
+	/// Balance of `who` in the given asset.
+	fn balance(asset_id: Self::AssetId, who: &AccountId) -> Self::Balance;
+
+	/// Total issuance of the given asset.
+	fn total_supply(asset_id: Self::AssetId) -> Self::Balance;
+
+	/// Move `amount` of the asset from `from` to `to`.
+	fn transfer(
+		asset_id: Self::AssetId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Self::Balance,
+	) -> DispatchResult;
+
+	/// Create `amount` of the asset in the account `who`, increasing the total supply.
+	fn deposit(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+
+	/// Destroy `amount` of the asset in the account `who`, decreasing the total supply.
+	fn withdraw(asset_id: Self::AssetId, who: &AccountId, amount: Self::Balance) -> DispatchResult;
+}
+
+/// Handler for asset balances too small to be kept in an account.
+pub trait OnAssetDust<AssetId, Balance> {
+	/// Handle `amount` of the asset that was removed from an account as dust.
+	fn on_dust(asset_id: AssetId, amount: Balance);
+}
+
+/// Burns the dust.
+impl<AssetId, Balance> OnAssetDust<AssetId, Balance> for () {
+	fn on_dust(_asset_id: AssetId, _amount: Balance) {}
+}
+
+/// Details of an asset in `pallet_assets`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetDetails<AccountId, Balance> {
+	/// Total issuance of the asset.
+	pub total_supply: Balance,
+	/// The account that issued the asset, if known.
+	pub owner: Option<AccountId>,
+}
+
+/// Human-readable information about an asset.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct AssetMetadata {
+	/// Name of the asset.
+	pub name: Vec<u8>,
+	/// Ticker symbol of the asset.
+	pub symbol: Vec<u8>,
+	/// Number of decimals of the balances of the asset.
+	pub decimals: u8,
+	/// Hash of the logo of the asset.
+	pub logo: Option<H256>,
+}
+
+/// The cross-chain reserves of an asset, checked against its total supply.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ReserveReconciliation<Balance> {
+	/// Total issuance of the asset on this parachain.
+	pub total_supply: Balance,
+	/// Amount of a local asset sent to every sibling parachain and not returned.
+	pub reserves: Vec<(ParaId, Balance)>,
+	/// Amount of a foreign asset held for this parachain on its home parachain.
+	pub backing: Option<Balance>,
+	/// Amount not covered: the total supply of a foreign asset above its backing, or the
+	/// reserves of a local asset missing from the sovereign accounts holding them.
+	pub shortfall: Balance,
+}
+
+/// Where an inbound message comes from.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum MessageOrigin {
+	/// A downward message from the relay chain.
+	Relay,
+	/// An XCMP message from a sibling parachain.
+	Parachain(ParaId),
+}
+
+/// State of an HRMP channel, as far as this parachain requested it from the relay chain.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum ChannelState {
+	/// This parachain requested to open the channel to the recipient.
+	OpenRequested {
+		/// The most messages the channel may hold.
+		max_capacity: u32,
+		/// The largest message the channel may carry, in bytes.
+		max_message_size: u32,
+	},
+	/// This parachain accepted the request of the sender to open the channel.
+	Accepted,
+	/// This parachain requested to close the channel.
+	CloseRequested,
+}
+
+/// An HRMP channel this parachain sends or receives messages through.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct HrmpChannel {
+	/// The parachain sending through the channel.
+	pub sender: ParaId,
+	/// The parachain receiving through the channel.
+	pub recipient: ParaId,
+	/// State of the channel.
+	pub state: ChannelState,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Access to the fee multiplier of the transaction payment pallet.
+	pub trait FeeMultiplierApi {
+		/// The multiplier applied to the weight fee of transactions in the next block.
+		fn next_fee_multiplier() -> FixedU128;
+	}
+
+	/// Access to the queue of inbound messages.
+	pub trait InboundQueueApi {
+		/// The number of inbound messages waiting to be handled.
+		fn inbound_queue_depth() -> u64;
+	}
+
+	/// Access to the HRMP channels of this parachain.
+	pub trait HrmpChannelsApi {
+		/// The channels requested, accepted or being closed by this parachain.
+		fn hrmp_channels() -> Vec<HrmpChannel>;
+	}
+
+	/// Access to the assets of `pallet_assets`.
+	pub trait AssetsApi<AccountId, AssetId, Balance>
+	where
+		AccountId: Codec,
+		AssetId: Codec,
+		Balance: Codec,
+	{
+		/// Balance of `who` in the given asset.
+		fn balance(asset_id: AssetId, who: AccountId) -> Balance;
+
+		/// Total issuance of the given asset.
+		fn total_supply(asset_id: AssetId) -> Balance;
+
+		/// Details of the given asset, `None` if it was never issued.
+		fn asset_details(asset_id: AssetId) -> Option<AssetDetails<AccountId, Balance>>;
+
+		/// Metadata of the given asset, if set.
+		fn asset_metadata(asset_id: AssetId) -> Option<AssetMetadata>;
+
+		/// The assets `who` holds and its balance of each.
+		fn account_assets(who: AccountId) -> Vec<(AssetId, Balance)>;
+
+		/// The local asset an asset of a sibling parachain is minted as, if registered.
+		fn local_asset_id(para_id: ParaId, remote_id: AssetId) -> Option<AssetId>;
+
+		/// The sibling parachain and remote id of a local asset that represents a foreign one.
+		fn foreign_asset(asset_id: AssetId) -> Option<(ParaId, AssetId)>;
+
+		/// The cross-chain reserves of the given asset, checked against its total supply.
+		fn reconcile_reserves(asset_id: AssetId) -> ReserveReconciliation<Balance>;
+	}
+}
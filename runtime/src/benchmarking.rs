@@ -0,0 +1,88 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Benchmarks of the base weights of the runtime.
+//!
+//! `block` measures initializing and finalizing an empty block, the pallet hooks included.
+//! `extrinsic` measures applying a signed `System::remark` with an empty payload, which covers
+//! signature verification, the signed extensions and fee payment. The results are the values of
+//! `BlockExecutionWeight` and `ExtrinsicBaseWeight`.
+
+use super::{
+    AccountId, Balances, Call, Executive, Header, Runtime, SignedExtra, System, UncheckedExtrinsic,
+    DOLLARS,
+};
+use codec::Encode;
+use frame_benchmarking::benchmarks;
+use frame_support::traits::Currency;
+use sp_core::crypto::KeyTypeId;
+use sp_runtime::{
+    generic::{Era, SignedPayload},
+    traits::{Header as HeaderT, IdentifyAccount},
+    MultiSignature, MultiSigner,
+};
+use sp_std::prelude::*;
+
+/// Key type of the key signing the benchmarked extrinsic.
+const BENCHMARK_KEY: KeyTypeId = KeyTypeId(*b"bnch");
+
+pub struct Module<T: Trait>(frame_system::Module<T>);
+pub trait Trait: frame_system::Trait {}
+
+/// Header of the block following the current one.
+fn next_header() -> Header {
+    Header::new(
+        System::block_number() + 1,
+        Default::default(),
+        Default::default(),
+        System::parent_hash(),
+        Default::default(),
+    )
+}
+
+/// A funded account signing an empty remark with the signed extensions of the runtime.
+fn signed_remark() -> Result<UncheckedExtrinsic, &'static str> {
+    let public = sp_io::crypto::sr25519_generate(BENCHMARK_KEY, None);
+    let signer: AccountId = MultiSigner::from(public).into_account();
+    Balances::make_free_balance_be(&signer, 1_000 * DOLLARS);
+
+    let extra: SignedExtra = (
+        frame_system::CheckSpecVersion::new(),
+        frame_system::CheckGenesis::new(),
+        frame_system::CheckEra::from(Era::Immortal),
+        frame_system::CheckNonce::from(System::account_nonce(&signer)),
+        frame_system::CheckWeight::new(),
+        pallet_asset_tx_payment::ChargeAssetTxPayment::from(0, None),
+    );
+    let call = Call::System(frame_system::Call::remark(vec![]));
+    let payload = SignedPayload::new(call, extra).map_err(|_| "Invalid signed extra")?;
+    let signature = payload
+        .using_encoded(|payload| sp_io::crypto::sr25519_sign(BENCHMARK_KEY, &public, payload))
+        .ok_or("Signing the extrinsic failed")?;
+    let (call, extra, _) = payload.deconstruct();
+
+    Ok(UncheckedExtrinsic::new_signed(
+        call,
+        signer,
+        MultiSignature::from(signature),
+        extra,
+    ))
+}
+
+benchmarks! {
+    _ { }
+
+    block {
+        let header = next_header();
+    }: {
+        Executive::initialize_block(&header);
+        System::finalize();
+    }
+
+    extrinsic {
+        let xt = signed_remark()?;
+    }: {
+        Executive::apply_extrinsic(xt)
+            .map_err(|_| "Invalid extrinsic")?
+            .map_err(|_| "Extrinsic failed")?;
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Implementations of traits that glue the pallets of the runtime together.
+
+use crate::{
+    token_dealer, AccountId, AssetDealer, AssetId, AssetIndex, AssetMetadata, AssetTreasury,
+    Assets, Aura, AvailableBlockRatio, Balance, Call, ChainOrigin, CollatorRewards, Council,
+    MaximumBlockWeight, MessageBroker, RemoteCall, Runtime, SafeMode, SafeModeExempt, Session,
+    System, TokenDealer, TreasuryAccount, XCMPSender, DOLLARS, HOURS,
+};
+use codec::{Decode, Encode, Input, Output};
+use cumulus_primitives::{
+    xcmp::{XCMPMessageHandler, XCMPMessageSender},
+    DownwardMessage, DownwardMessageHandler, ParaId, UpwardMessageOrigin, UpwardMessageSender,
+};
+use frame_support::{
+    traits::{Contains, Filter, FindAuthor, Get},
+    weights::{
+        constants::RocksDbWeight, Weight, WeightToFeeCoefficient, WeightToFeeCoefficients,
+        WeightToFeePolynomial,
+    },
+    StorageMap, StorageValue,
+};
+use pallet_asset_calls::OnAssetChange;
+use pallet_asset_registry::AssetIdAllocator;
+use pallet_asset_tx_payment::OnAssetFee;
+use pallet_collator_selection::ValidatorRegistration;
+use pallet_hrmp_channels::RelayHrmpCall;
+use pallet_remote_call::Transact;
+use pallet_safe_mode::{PauseTarget, PausedPallets};
+use parachain_primitives::{MultiAsset, OnAssetDust, AUTHOR_ENGINE_ID};
+use smallvec::smallvec;
+use sp_consensus_aura::{sr25519::AuthoritySignature, AURA_ENGINE_ID};
+use sp_runtime::{
+    traits::{CheckedAdd, CheckedSub, Saturating, Zero},
+    ConsensusEngineId, DispatchError, DispatchResult, Perbill, RuntimeAppPublic, RuntimeDebug,
+};
+use sp_std::{marker::PhantomData, prelude::*};
+
+type AssetBalances = <Assets as pallet_assets::Store>::Balances;
+type AssetTotalSupply = <Assets as pallet_assets::Store>::TotalSupply;
+type AssetNextId = <Assets as pallet_assets::Store>::NextAssetId;
+
+/// `MultiAsset` on top of the storage of `pallet_assets`.
+///
+/// Changed balances are reported to `AssetChangeHooks`, like the ones changed by `AssetCalls`.
+pub struct AssetsAdapter;
+
+impl MultiAsset<AccountId> for AssetsAdapter {
+    type AssetId = AssetId;
+    type Balance = Balance;
+
+    fn balance(asset_id: AssetId, who: &AccountId) -> Balance {
+        AssetBalances::get((asset_id, who.clone()))
+    }
+
+    fn total_supply(asset_id: AssetId) -> Balance {
+        AssetTotalSupply::get(asset_id)
+    }
+
+    fn transfer(
+        asset_id: AssetId,
+        from: &AccountId,
+        to: &AccountId,
+        amount: Balance,
+    ) -> DispatchResult {
+        if amount.is_zero() || from == to {
+            return Ok(());
+        }
+
+        let from_balance = Self::balance(asset_id, from)
+            .checked_sub(amount)
+            .ok_or(DispatchError::Other("Insufficient asset balance"))?;
+        let to_balance = Self::balance(asset_id, to)
+            .checked_add(amount)
+            .ok_or(DispatchError::Other("Asset balance overflow"))?;
+
+        AssetBalances::insert((asset_id, from.clone()), from_balance);
+        AssetBalances::insert((asset_id, to.clone()), to_balance);
+        AssetChangeHooks::on_debited(asset_id, from);
+        AssetChangeHooks::on_credited(asset_id, to);
+
+        Ok(())
+    }
+
+    fn deposit(asset_id: AssetId, who: &AccountId, amount: Balance) -> DispatchResult {
+        let supply = Self::total_supply(asset_id)
+            .checked_add(amount)
+            .ok_or(DispatchError::Other("Asset total supply overflow"))?;
+        let balance = Self::balance(asset_id, who)
+            .checked_add(amount)
+            .ok_or(DispatchError::Other("Asset balance overflow"))?;
+
+        AssetTotalSupply::insert(asset_id, supply);
+        AssetBalances::insert((asset_id, who.clone()), balance);
+        AssetChangeHooks::on_credited(asset_id, who);
+
+        Ok(())
+    }
+
+    fn withdraw(asset_id: AssetId, who: &AccountId, amount: Balance) -> DispatchResult {
+        let balance = Self::balance(asset_id, who)
+            .checked_sub(amount)
+            .ok_or(DispatchError::Other("Insufficient asset balance"))?;
+
+        AssetBalances::insert((asset_id, who.clone()), balance);
+        AssetTotalSupply::mutate(asset_id, |supply| *supply = supply.saturating_sub(amount));
+        AssetChangeHooks::on_debited(asset_id, who);
+
+        Ok(())
+    }
+}
+
+/// Keeps the asset index and the owners of `AssetMetadata` up to date and reaps asset dust, for
+/// every asset issued and every balance changed by `AssetsAdapter` or `AssetCalls`.
+///
+/// A balance left in the account an asset is moved out of that `AssetTreasury` considers dust is
+/// removed and handed to the asset treasury.
+pub struct AssetChangeHooks;
+
+impl AssetChangeHooks {
+    fn reap_dust(asset_id: AssetId, who: &AccountId) {
+        let remaining = AssetBalances::get((asset_id, who.clone()));
+
+        if AssetTreasury::is_dust(asset_id, remaining) {
+            AssetBalances::remove((asset_id, who.clone()));
+            AssetTotalSupply::mutate(asset_id, |supply| *supply = supply.saturating_sub(remaining));
+            AssetTreasury::on_dust(asset_id, remaining);
+        }
+    }
+}
+
+impl OnAssetChange<AccountId, AssetId> for AssetChangeHooks {
+    fn on_issued(asset_id: AssetId, who: &AccountId) {
+        AssetMetadata::note_issued(asset_id, who);
+    }
+
+    fn on_credited(asset_id: AssetId, who: &AccountId) {
+        AssetIndex::note(asset_id, who);
+    }
+
+    fn on_debited(asset_id: AssetId, who: &AccountId) {
+        Self::reap_dust(asset_id, who);
+        AssetIndex::note(asset_id, who);
+    }
+
+    fn weight() -> Weight {
+        // Reaping dust and depositing it in the treasury, then updating the index. Noting the
+        // owner of an issued asset weighs less.
+        RocksDbWeight::get().reads_writes(5, 5)
+    }
+}
+
+/// The XCMP messages of this parachain: the transfers of `TokenDealer` and `AssetDealer`, and the
+/// calls of `RemoteCall`.
+///
+/// `TransferToken` is encoded like `token_dealer::XCMPMessage::TransferToken`, so transfers are
+/// exchanged with parachains that only know `token_dealer` messages.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub enum XCMPMessage {
+    /// Transfer tokens to an account, see `token_dealer::XCMPMessage::TransferToken`.
+    TransferToken(AccountId, Balance, Option<AssetId>),
+    /// Dispatch a call with the origin of the sending parachain.
+    Transact(Transact<Balance>),
+}
+
+impl From<token_dealer::XCMPMessage<AccountId, Balance, AssetId>> for XCMPMessage {
+    fn from(msg: token_dealer::XCMPMessage<AccountId, Balance, AssetId>) -> Self {
+        match msg {
+            token_dealer::XCMPMessage::TransferToken(dest, amount, asset_id) => {
+                XCMPMessage::TransferToken(dest, amount, asset_id)
+            }
+        }
+    }
+}
+
+impl From<Transact<Balance>> for XCMPMessage {
+    fn from(transact: Transact<Balance>) -> Self {
+        XCMPMessage::Transact(transact)
+    }
+}
+
+/// Sends the transfers of `TokenDealer` and `AssetDealer` through `MessageBroker`, the XCMP ones
+/// numbered by `ReplayGuard`.
+pub struct TransferSender;
+
+impl XCMPMessageSender<token_dealer::XCMPMessage<AccountId, Balance, AssetId>> for TransferSender {
+    fn send_xcmp_message(
+        dest: ParaId,
+        msg: &token_dealer::XCMPMessage<AccountId, Balance, AssetId>,
+    ) -> Result<(), ()> {
+        XCMPSender::send_xcmp_message(dest, &msg.clone().into())
+    }
+}
+
+impl UpwardMessageSender<token_dealer::upward_messages::RococoUpwardMessage> for TransferSender {
+    fn send_upward_message(
+        msg: &token_dealer::upward_messages::RococoUpwardMessage,
+        origin: UpwardMessageOrigin,
+    ) -> Result<(), ()> {
+        MessageBroker::send_upward_message(&UpwardMessage(msg.encode()), origin)
+    }
+}
+
+/// The upward messages of this parachain: an encoded call of the relay chain, either a transfer
+/// of `TokenDealer` or an HRMP request of `HrmpChannels`.
+#[derive(Clone, PartialEq, RuntimeDebug)]
+pub struct UpwardMessage(pub Vec<u8>);
+
+impl Encode for UpwardMessage {
+    fn encode_to<T: Output>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+impl Decode for UpwardMessage {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let len = input.remaining_len()?.ok_or("upward message of unknown length")?;
+        let mut call = vec![0; len];
+        input.read(&mut call)?;
+        Ok(UpwardMessage(call))
+    }
+}
+
+impl From<RelayHrmpCall> for UpwardMessage {
+    fn from(call: RelayHrmpCall) -> Self {
+        UpwardMessage(call.encode())
+    }
+}
+
+/// Routes the downward messages: calls of the relay chain to `ChainOrigin`, transfers to
+/// `TokenDealer`.
+pub struct DownwardRouter;
+
+impl DownwardMessageHandler for DownwardRouter {
+    fn handle_downward_message(msg: &DownwardMessage) {
+        match msg {
+            DownwardMessage::ParachainSpecific(_) => ChainOrigin::handle_downward_message(msg),
+            _ => TokenDealer::handle_downward_message(msg),
+        }
+    }
+}
+
+/// Routes the XCMP messages of this parachain.
+///
+/// Native tokens are handled by `TokenDealer`, assets by `AssetDealer`. Native amounts quarantined
+/// by `DepositGuard` or that cannot be deposited are not handed to `TokenDealer`. Calls are
+/// dispatched by `RemoteCall`.
+pub struct XcmpRouter;
+
+impl XCMPMessageHandler<XCMPMessage> for XcmpRouter {
+    fn handle_xcmp_message(src: ParaId, msg: &XCMPMessage) {
+        match msg {
+            XCMPMessage::TransferToken(dest, amount, Some(asset_id)) => {
+                AssetDealer::receive(src, *asset_id, dest.clone(), *amount)
+            }
+            XCMPMessage::TransferToken(dest, amount, None) => {
+                if AssetDealer::receive_native(src, dest, *amount) {
+                    let msg = token_dealer::XCMPMessage::TransferToken(dest.clone(), *amount, None);
+                    TokenDealer::handle_xcmp_message(src, &msg)
+                }
+            }
+            XCMPMessage::Transact(transact) => RemoteCall::transact(src, transact),
+        }
+    }
+}
+
+/// Allocates asset ids from the counter of `pallet_assets`, so they never collide with issued
+/// assets.
+pub struct NextAssetId;
+
+impl AssetIdAllocator<AssetId> for NextAssetId {
+    fn allocate() -> AssetId {
+        AssetNextId::mutate(|next| {
+            let id = *next;
+            *next = next.saturating_add(1);
+            id
+        })
+    }
+
+    fn is_allocated(asset_id: AssetId) -> bool {
+        asset_id < AssetNextId::get()
+    }
+
+    fn reserve(asset_id: AssetId) {
+        AssetNextId::mutate(|next| *next = (*next).max(asset_id.saturating_add(1)));
+    }
+}
+
+/// Pays out fees paid in assets like `CollatorRewards` pays out native fees: the shares of the
+/// block author go to the author, the rest to the treasury.
+pub struct AssetFeesToAuthor;
+
+impl OnAssetFee<AccountId, AssetId, Balance> for AssetFeesToAuthor {
+    fn on_asset_fee(pot: &AccountId, asset_id: AssetId, fee: Balance, tip: Balance) {
+        let to_author = match CollatorRewards::author() {
+            Some(author) => {
+                let amount = CollatorRewards::fee_author_share() * fee
+                    + CollatorRewards::tip_author_share() * tip;
+                match AssetsAdapter::transfer(asset_id, pot, &author, amount) {
+                    Ok(()) => amount,
+                    Err(_) => Zero::zero(),
+                }
+            }
+            None => Zero::zero(),
+        };
+
+        let rest = fee.saturating_add(tip).saturating_sub(to_author);
+        let _ = AssetsAdapter::transfer(asset_id, pot, &TreasuryAccount::get(), rest);
+    }
+}
+
+/// The members of the council.
+pub struct CouncilMembers;
+
+impl Contains<AccountId> for CouncilMembers {
+    fn sorted_members() -> Vec<AccountId> {
+        Council::members()
+    }
+}
+
+/// Finds the author with `Inner` if the Aura slot of the block is signed by the authority it is
+/// assigned to.
+///
+/// The node rejects blocks with a bad signature on import. Checking again here means the relay
+/// chain, which only runs the runtime, never sees rewards paid to an unverified author.
+pub struct VerifiedAuthor<Inner>(PhantomData<Inner>);
+
+impl<Inner: FindAuthor<AccountId>> FindAuthor<AccountId> for VerifiedAuthor<Inner> {
+    fn find_author<'a, I>(digests: I) -> Option<AccountId>
+    where
+        I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+    {
+        let digests = digests.into_iter().collect::<Vec<_>>();
+        let slot = digests.iter().find_map(|&(id, mut data)| match id {
+            AURA_ENGINE_ID => u64::decode(&mut data).ok(),
+            _ => None,
+        })?;
+        let signature = digests.iter().find_map(|&(id, mut data)| match id {
+            AUTHOR_ENGINE_ID => AuthoritySignature::decode(&mut data).ok(),
+            _ => None,
+        })?;
+
+        let authorities = Aura::authorities();
+        if authorities.is_empty() {
+            return None;
+        }
+        let owner = &authorities[(slot % authorities.len() as u64) as usize];
+        if !owner.verify(&(System::parent_hash(), slot).encode(), &signature) {
+            return None;
+        }
+
+        Inner::find_author(digests)
+    }
+}
+
+/// Collators are registered once they have set their session keys.
+pub struct SessionKeysRegistered;
+
+impl ValidatorRegistration<AccountId> for SessionKeysRegistered {
+    fn is_registered(who: &AccountId) -> bool {
+        <Session as pallet_session::Store>::NextKeys::contains_key(who)
+    }
+}
+
+/// Lets calls through unless `SafeMode` pauses them.
+///
+/// The pallets in `SafeModeExempt` are never paused, so that blocks keep being produced and safe
+/// mode can always be lifted.
+pub struct BaseFilter;
+
+impl Filter<Call> for BaseFilter {
+    fn filter(call: &Call) -> bool {
+        match call {
+            Call::TokenDealer(_)
+            | Call::AssetDealer(pallet_asset_dealer::Call::transfer_to_parachain(..))
+            | Call::AssetDealer(pallet_asset_dealer::Call::refund(..))
+            | Call::DepositGuard(pallet_deposit_guard::Call::send_back(..))
+            | Call::RemoteCall(pallet_remote_call::Call::send(..))
+                if SafeMode::is_paused(&PauseTarget::Outbound) =>
+            {
+                false
+            }
+            _ => PausedPallets::<Runtime, SafeModeExempt>::filter(call),
+        }
+    }
+}
+
+/// Converts weight to fee, linearly.
+///
+/// Tuned so that filling the normal dispatch class of every block for an hour, that is
+/// `HOURS` blocks of 6 seconds, costs `10_000 * DOLLARS` at a fee multiplier of one.
+pub struct WeightToFee;
+
+impl WeightToFeePolynomial for WeightToFee {
+    type Balance = Balance;
+
+    fn polynomial() -> WeightToFeeCoefficients<Self::Balance> {
+        let p = 10_000 * DOLLARS;
+        let q = Balance::from(HOURS)
+            * Balance::from(AvailableBlockRatio::get() * MaximumBlockWeight::get());
+
+        smallvec![WeightToFeeCoefficient {
+            degree: 1,
+            negative: false,
+            coeff_frac: Perbill::from_rational_approximation(p % q, q),
+            coeff_integer: p / q,
+        }]
+    }
+}
@@ -8,28 +8,54 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_api::impl_runtime_apis;
-use sp_core::OpaqueMetadata;
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_core::{
+    u32_trait::{_1, _2, _3, _4},
+    OpaqueMetadata,
+};
 use sp_runtime::{
     create_runtime_str, generic, impl_opaque_keys,
-    traits::{BlakeTwo256, Block as BlockT, IdentifyAccount, IdentityLookup, Saturating, Verify},
+    traits::{
+        BlakeTwo256, Block as BlockT, ConvertInto, IdentifyAccount, IdentityLookup, Saturating,
+        Verify, Zero,
+    },
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, MultiSignature,
+    ApplyExtrinsicResult, FixedPointNumber, ModuleId, MultiSignature,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 
+use frame_system::{EnsureOneOf, EnsureRoot};
+use cumulus_primitives::ParaId;
+use parachain_primitives::{AssetDetails, HrmpChannel, MultiAsset, ReserveReconciliation};
+use pallet_transaction_payment::{Multiplier, TargetedFeeAdjustment};
+use pallet_chain_origin::EnsureRelay;
+use pallet_inbound_queue::Enqueue;
+use pallet_replay_guard::{Deduplicated, Sequenced, SequencedSender};
+
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
     construct_runtime, parameter_types,
     traits::Randomness,
-    weights::{constants::WEIGHT_PER_SECOND, IdentityFee, Weight},
+    weights::{
+        constants::{BlockExecutionWeight, RocksDbWeight, WEIGHT_PER_MICROS, WEIGHT_PER_SECOND},
+        IdentityFee, Weight,
+    },
     StorageValue,
 };
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
-pub use sp_runtime::{Perbill, Permill};
+pub use sp_runtime::{Perbill, Percent, Permill, Perquintill};
+
+mod impls;
+pub use impls::{AssetsAdapter, UpwardMessage, WeightToFee, XCMPMessage, XcmpRouter};
+pub use pallet_asset_dealer::TransferMode;
+pub use pallet_asset_registry::RegistrationPolicy;
+mod weights;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
 /// Import the message pallet.
 pub use pallet_generic_token_dealer as token_dealer;
@@ -74,10 +100,12 @@ pub mod opaque {
     pub type Block = generic::Block<Header, UncheckedExtrinsic>;
 }
 
-pub type SessionHandlers = ();
+pub type SessionHandlers = (Aura,);
 
 impl_opaque_keys! {
-    pub struct SessionKeys {}
+    pub struct SessionKeys {
+        pub aura: Aura,
+    }
 }
 
 /// This runtime version.
@@ -102,6 +130,11 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// Currency units, expressed in the smallest unit of `Balance`.
+pub const MILLICENTS: Balance = 1_000_000_000;
+pub const CENTS: Balance = 1_000 * MILLICENTS;
+pub const DOLLARS: Balance = 100 * CENTS;
+
 // 1 in 4 blocks (on average, not counting collisions) will be primary babe blocks.
 pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
 
@@ -123,7 +156,10 @@ parameter_types! {
     pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
     pub const MaximumBlockLength: u32 = 5 * 1024 * 1024;
     pub const Version: RuntimeVersion = VERSION;
-    pub const ExtrinsicBaseWeight: Weight = 10_000_000;
+    /// Weight of an empty extrinsic. Placeholder: this is the Substrate reference value, not a
+    /// measurement. Replace it with the `extrinsic` result of `benchmark --pallet overhead` run on
+    /// collator hardware.
+    pub const ExtrinsicBaseWeight: Weight = 125 * WEIGHT_PER_MICROS;
 }
 
 impl frame_system::Trait for Runtime {
@@ -161,11 +197,11 @@ impl frame_system::Trait for Runtime {
     type AccountData = pallet_balances::AccountData<Balance>;
     type OnNewAccount = ();
     type OnKilledAccount = ();
-    type DbWeight = ();
+    type DbWeight = RocksDbWeight;
     type ExtrinsicBaseWeight = ExtrinsicBaseWeight;
-    type BlockExecutionWeight = ();
+    type BlockExecutionWeight = BlockExecutionWeight;
     type MaximumExtrinsicWeight = MaximumExtrinsicWeight;
-    type BaseCallFilter = ();
+    type BaseCallFilter = impls::BaseFilter;
     type SystemWeightInfo = ();
 }
 
@@ -176,9 +212,9 @@ parameter_types! {
 impl pallet_timestamp::Trait for Runtime {
     /// A timestamp: milliseconds since the unix epoch.
     type Moment = u64;
-    type OnTimestampSet = ();
+    type OnTimestampSet = Aura;
     type MinimumPeriod = MinimumPeriod;
-    type WeightInfo = ();
+    type WeightInfo = weights::pallet_timestamp::WeightInfo;
 }
 
 parameter_types! {
@@ -186,6 +222,10 @@ parameter_types! {
     pub const TransferFee: u128 = 0;
     pub const CreationFee: u128 = 0;
     pub const TransactionByteFee: u128 = 1;
+    /// Fees go up when blocks are more than a quarter full and down when they are less.
+    pub const TargetBlockFullness: Perquintill = Perquintill::from_percent(25);
+    pub AdjustmentVariable: Multiplier = Multiplier::saturating_from_rational(3, 100_000);
+    pub MinimumMultiplier: Multiplier = Multiplier::saturating_from_rational(1, 1_000_000_000u128);
 }
 
 impl pallet_assets::Trait for Runtime {
@@ -196,18 +236,188 @@ impl pallet_assets::Trait for Runtime {
 impl pallet_balances::Trait for Runtime {
     type Balance = Balance;
     type Event = Event;
-    type DustRemoval = ();
+    type DustRemoval = Treasury;
     type ExistentialDeposit = ExistentialDeposit;
     type AccountStore = System;
-    type WeightInfo = ();
+    type WeightInfo = weights::pallet_balances::WeightInfo;
 }
 
 impl pallet_transaction_payment::Trait for Runtime {
     type Currency = Balances;
-    type OnTransactionPayment = ();
+    type OnTransactionPayment = CollatorRewards;
     type TransactionByteFee = TransactionByteFee;
-    type WeightToFee = IdentityFee<Balance>;
-    type FeeMultiplierUpdate = ();
+    type WeightToFee = WeightToFee;
+    type FeeMultiplierUpdate =
+        TargetedFeeAdjustment<Self, TargetBlockFullness, AdjustmentVariable, MinimumMultiplier>;
+}
+
+impl pallet_aura::Trait for Runtime {
+    type AuthorityId = AuraId;
+}
+
+parameter_types! {
+    pub const UncleGenerations: BlockNumber = 0;
+}
+
+impl pallet_authorship::Trait for Runtime {
+    type FindAuthor = impls::VerifiedAuthor<pallet_session::FindAccountFromAuthorIndex<Self, Aura>>;
+    type UncleGenerations = UncleGenerations;
+    type FilterUncle = ();
+    type EventHandler = CollatorSelection;
+}
+
+parameter_types! {
+    pub const Period: BlockNumber = 6 * HOURS;
+    pub const Offset: BlockNumber = 0;
+    pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+}
+
+impl pallet_session::Trait for Runtime {
+    type Event = Event;
+    type ValidatorId = AccountId;
+    type ValidatorIdOf = ConvertInto;
+    type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+    type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
+    type SessionManager = CollatorSelection;
+    type SessionHandler = SessionHandlers;
+    type Keys = SessionKeys;
+    type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const MaxCandidates: u32 = 100;
+    pub const MaxInvulnerables: u32 = 20;
+    /// Candidates that did not author a block for two sessions are kicked.
+    pub KickThreshold: BlockNumber = 2 * Period::get();
+}
+
+impl pallet_collator_selection::Trait for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+    type MaxCandidates = MaxCandidates;
+    type MaxInvulnerables = MaxInvulnerables;
+    type KickThreshold = KickThreshold;
+    type ValidatorRegistration = impls::SessionKeysRegistered;
+}
+
+parameter_types! {
+    pub const ProposalBond: Permill = Permill::from_percent(5);
+    pub const ProposalBondMinimum: Balance = DOLLARS;
+    pub const SpendPeriod: BlockNumber = DAYS;
+    pub const Burn: Permill = Permill::from_percent(0);
+    pub const TipCountdown: BlockNumber = DAYS;
+    pub const TipFindersFee: Percent = Percent::from_percent(20);
+    pub const TipReportDepositBase: Balance = DOLLARS;
+    pub const TipReportDepositPerByte: Balance = CENTS;
+    pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+}
+
+impl pallet_treasury::Trait for Runtime {
+    type ModuleId = TreasuryModuleId;
+    type Currency = Balances;
+    type ApproveOrigin = EnsureRootOrHalfCouncil;
+    type RejectOrigin = EnsureRootOrHalfCouncil;
+    type Tippers = impls::CouncilMembers;
+    type TipCountdown = TipCountdown;
+    type TipFindersFee = TipFindersFee;
+    type TipReportDepositBase = TipReportDepositBase;
+    type TipReportDepositPerByte = TipReportDepositPerByte;
+    type Event = Event;
+    type ProposalRejection = Treasury;
+    type ProposalBond = ProposalBond;
+    type ProposalBondMinimum = ProposalBondMinimum;
+    type SpendPeriod = SpendPeriod;
+    type Burn = Burn;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const AssetProposalBond: Balance = 10 * DOLLARS;
+    pub TreasuryAccount: AccountId = Treasury::account_id();
+}
+
+impl pallet_asset_treasury::Trait for Runtime {
+    type Event = Event;
+    type Assets = AssetsAdapter;
+    type Currency = Balances;
+    type ApproveOrigin = EnsureRootOrHalfCouncil;
+    type RejectOrigin = EnsureRootOrHalfCouncil;
+    type ProposalBond = AssetProposalBond;
+    type OnSlash = Treasury;
+    type TreasuryAccount = TreasuryAccount;
+}
+
+impl pallet_collator_rewards::Trait for Runtime {
+    type Event = Event;
+    type Currency = Balances;
+    type FindAuthor = impls::VerifiedAuthor<pallet_session::FindAccountFromAuthorIndex<Self, Aura>>;
+    type Beneficiary = Treasury;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+}
+
+parameter_types! {
+    pub const AssetTxPaymentModuleId: ModuleId = ModuleId(*b"py/astxp");
+}
+
+impl pallet_asset_tx_payment::Trait for Runtime {
+    type Event = Event;
+    type Assets = AssetsAdapter;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+    type OnAssetFee = impls::AssetFeesToAuthor;
+    type ModuleId = AssetTxPaymentModuleId;
+}
+
+impl pallet_asset_index::Trait for Runtime {
+    type Assets = AssetsAdapter;
+}
+
+impl pallet_asset_calls::Trait for Runtime {
+    type OnAssetChange = impls::AssetChangeHooks;
+}
+
+parameter_types! {
+    pub const MetadataDepositBase: Balance = DOLLARS;
+    pub const MetadataDepositPerByte: Balance = 10 * CENTS;
+    pub const StringLimit: u32 = 50;
+}
+
+impl pallet_asset_registry::Trait for Runtime {
+    type Event = Event;
+    type Assets = AssetsAdapter;
+    type AssetIdAllocator = impls::NextAssetId;
+    type RegisterOrigin = EnsureRootOrHalfCouncil;
+}
+
+impl pallet_asset_dealer::Trait for Runtime {
+    type Event = Event;
+    type XCMPMessageSender = impls::TransferSender;
+    type Currency = Balances;
+    type UpdateOrigin = EnsureRootOrHalfCouncil;
+    type DepositFilter = DepositGuard;
+    type OutboundPaused = pallet_safe_mode::OutboundPaused<Runtime>;
+}
+
+parameter_types! {
+    pub const RateLimitWindow: BlockNumber = HOURS;
+    pub const MaxQuarantinedPerOrigin: u32 = 100;
+}
+
+impl pallet_deposit_guard::Trait for Runtime {
+    type Event = Event;
+    type RateLimitWindow = RateLimitWindow;
+    type MaxQuarantinedPerOrigin = MaxQuarantinedPerOrigin;
+}
+
+impl pallet_asset_metadata::Trait for Runtime {
+    type Event = Event;
+    type Assets = AssetsAdapter;
+    type Currency = Balances;
+    type ForceOrigin = EnsureRootOrHalfCouncil;
+    type MetadataDepositBase = MetadataDepositBase;
+    type MetadataDepositPerByte = MetadataDepositPerByte;
+    type StringLimit = StringLimit;
 }
 
 impl pallet_sudo::Trait for Runtime {
@@ -215,6 +425,129 @@ impl pallet_sudo::Trait for Runtime {
     type Event = Event;
 }
 
+// Governance
+//
+// The council, the technical committee and democracy are inert as long as the collectives have no
+// members, so the chain can be run with `Sudo` alone. To retire `Sudo`:
+// 1. set the members of `Council` and `TechnicalCommittee` through `sudo(council.set_members)`
+//    and `sudo(technical_committee.set_members)`;
+// 2. remove `Sudo` from `construct_runtime!` and `impls::BaseFilter`, together with its genesis
+//    config, and enact the new runtime through `ParachainUpgrade`.
+// Referenda are dispatched with the root origin, so runtime upgrades through `ParachainUpgrade`
+// and all other root calls remain possible through democracy afterwards.
+
+/// Root, or more than half of the council.
+pub type EnsureRootOrHalfCouncil = EnsureOneOf<
+    AccountId,
+    EnsureRoot<AccountId>,
+    pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>,
+>;
+
+parameter_types! {
+    pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+    pub const CouncilMaxProposals: u32 = 100;
+    pub const CouncilMaxMembers: u32 = 100;
+}
+
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Trait<CouncilCollective> for Runtime {
+    type Origin = Origin;
+    type Proposal = Call;
+    type Event = Event;
+    type MotionDuration = CouncilMotionDuration;
+    type MaxProposals = CouncilMaxProposals;
+    type MaxMembers = CouncilMaxMembers;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const TechnicalMotionDuration: BlockNumber = 3 * DAYS;
+    pub const TechnicalMaxProposals: u32 = 100;
+    pub const TechnicalMaxMembers: u32 = 100;
+}
+
+type TechnicalCollective = pallet_collective::Instance2;
+impl pallet_collective::Trait<TechnicalCollective> for Runtime {
+    type Origin = Origin;
+    type Proposal = Call;
+    type Event = Event;
+    type MotionDuration = TechnicalMotionDuration;
+    type MaxProposals = TechnicalMaxProposals;
+    type MaxMembers = TechnicalMaxMembers;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * MaximumBlockWeight::get();
+    pub const MaxScheduledPerBlock: u32 = 50;
+}
+
+impl pallet_scheduler::Trait for Runtime {
+    type Event = Event;
+    type Origin = Origin;
+    type PalletsOrigin = OriginCaller;
+    type Call = Call;
+    type MaximumWeight = MaximumSchedulerWeight;
+    type ScheduleOrigin = EnsureRoot<AccountId>;
+    type MaxScheduledPerBlock = MaxScheduledPerBlock;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+    pub const VotingPeriod: BlockNumber = 7 * DAYS;
+    pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+    pub const InstantAllowed: bool = true;
+    pub const MinimumDeposit: Balance = 100 * DOLLARS;
+    pub const EnactmentPeriod: BlockNumber = 8 * DAYS;
+    pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+    pub const PreimageByteDeposit: Balance = CENTS;
+    pub const MaxVotes: u32 = 100;
+    pub const MaxProposals: u32 = 100;
+}
+
+impl pallet_democracy::Trait for Runtime {
+    type Proposal = Call;
+    type Event = Event;
+    type Currency = Balances;
+    type EnactmentPeriod = EnactmentPeriod;
+    type LaunchPeriod = LaunchPeriod;
+    type VotingPeriod = VotingPeriod;
+    type MinimumDeposit = MinimumDeposit;
+    /// A straight majority of the council can decide what their next motion is.
+    type ExternalOrigin =
+        pallet_collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+    /// A super-majority can have the next scheduled referendum be a straight majority-carries vote.
+    type ExternalMajorityOrigin =
+        pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+    /// A unanimous council can have the next scheduled referendum be a straight default-carries
+    /// (NTB) vote.
+    type ExternalDefaultOrigin =
+        pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+    /// Two thirds of the technical committee can have an external proposal tabled immediately
+    /// with a shorter voting period.
+    type FastTrackOrigin =
+        pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, TechnicalCollective>;
+    type InstantOrigin =
+        pallet_collective::EnsureProportionAtLeast<_1, _1, AccountId, TechnicalCollective>;
+    type InstantAllowed = InstantAllowed;
+    type FastTrackVotingPeriod = FastTrackVotingPeriod;
+    /// Two thirds of the council can cancel a passed referendum.
+    type CancellationOrigin =
+        pallet_collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+    /// Any member of the technical committee may veto a coming council proposal.
+    type VetoOrigin = pallet_collective::EnsureMember<AccountId, TechnicalCollective>;
+    type CooloffPeriod = CooloffPeriod;
+    type PreimageByteDeposit = PreimageByteDeposit;
+    type OperationalPreimageOrigin = pallet_collective::EnsureMember<AccountId, CouncilCollective>;
+    type Slash = Treasury;
+    type Scheduler = Scheduler;
+    type PalletsOrigin = OriginCaller;
+    type MaxVotes = MaxVotes;
+    type WeightInfo = ();
+    type MaxProposals = MaxProposals;
+}
+
 impl cumulus_parachain_upgrade::Trait for Runtime {
     type Event = Event;
     type OnValidationFunctionParams = ();
@@ -222,20 +555,123 @@ impl cumulus_parachain_upgrade::Trait for Runtime {
 
 impl cumulus_message_broker::Trait for Runtime {
     type Event = Event;
-    type DownwardMessageHandlers = TokenDealer;
-    type UpwardMessage = token_dealer::upward_messages::RococoUpwardMessage;
+    type DownwardMessageHandlers = Deduplicated<Runtime>;
+    type UpwardMessage = UpwardMessage;
     type ParachainId = ParachainInfo;
-    type XCMPMessage = token_dealer::XCMPMessage<AccountId, Balance, AssetId>;
-    type XCMPMessageHandlers = TokenDealer;
+    type XCMPMessage = Sequenced<XCMPMessage>;
+    type XCMPMessageHandlers = Deduplicated<Runtime>;
 }
 
-impl parachain_info::Trait for Runtime {}
+/// Sends XCMP messages through `MessageBroker`, numbered by `ReplayGuard`.
+pub type XCMPSender = SequencedSender<Runtime, MessageBroker>;
 
-impl token_dealer::Trait for Runtime {
+parameter_types! {
+    pub const ReplayWindow: BlockNumber = HOURS;
+    pub const MaxPrunedPerBlock: u32 = 100;
+    pub const MaxHeld: u32 = 1_000;
+}
+
+impl pallet_replay_guard::Trait for Runtime {
+    type Event = Event;
+    type XCMPMessage = XCMPMessage;
+    type DownwardMessageHandlers = Enqueue<Runtime>;
+    type XCMPMessageHandlers = Enqueue<Runtime>;
+    type ReleaseOrigin = EnsureRootOrHalfCouncil;
+    type MaxHeld = MaxHeld;
+    type ReplayWindow = ReplayWindow;
+    type MaxPrunedPerBlock = MaxPrunedPerBlock;
+}
+
+parameter_types! {
+    /// Reference value until the handlers of `TokenDealer` and `AssetDealer` are benchmarked.
+    pub const WeightPerInboundMessage: Weight = 200_000_000;
+    pub const MaxQueuedPerOrigin: u32 = 1_000;
+}
+
+impl pallet_inbound_queue::Trait for Runtime {
+    type Event = Event;
+    type XCMPMessage = XCMPMessage;
+    type DownwardMessageHandlers = impls::DownwardRouter;
+    type XCMPMessageHandlers = XcmpRouter;
+    type WeightPerMessage = WeightPerInboundMessage;
+    type Paused = pallet_safe_mode::InboundPaused<Runtime>;
+    type MaxQueuedPerOrigin = MaxQueuedPerOrigin;
+}
+
+/// Root, or more than half of the technical committee.
+pub type EnsureRootOrHalfTechnicalCommittee = EnsureOneOf<
+    AccountId,
+    EnsureRoot<AccountId>,
+    pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, TechnicalCollective>,
+>;
+
+parameter_types! {
+    pub const MaxPauseDuration: BlockNumber = 7 * DAYS;
+    /// Inherents, governance and `SafeMode` itself are never paused.
+    pub const SafeModeExempt: &'static [&'static str] = &[
+        "System",
+        "Timestamp",
+        "ParachainUpgrade",
+        "MessageBroker",
+        "Sudo",
+        "Council",
+        "TechnicalCommittee",
+        "Democracy",
+        "ChainOrigin",
+        "SafeMode",
+    ];
+}
+
+impl pallet_safe_mode::Trait for Runtime {
+    type Event = Event;
+    type SafeModeOrigin = EnsureRootOrHalfTechnicalCommittee;
+    type MaxPauseDuration = MaxPauseDuration;
+}
+
+impl pallet_remote_call::Trait for Runtime {
+    type Event = Event;
+    type Origin = Origin;
+    type Call = Call;
+    type XCMPMessage = XCMPMessage;
+    type XCMPMessageSender = XCMPSender;
+    type Currency = Balances;
+    type WeightToFee = WeightToFee;
+    type OnFee = CollatorRewards;
+    type SendOrigin = EnsureRootOrHalfCouncil;
+    // Inbound calls are dispatched by `InboundQueue`, which weighs every message the same.
+    type MaxTransactWeight = WeightPerInboundMessage;
+}
+
+/// Root, or the relay chain.
+pub type EnsureRootOrRelay = EnsureOneOf<AccountId, EnsureRoot<AccountId>, EnsureRelay<Origin>>;
+
+impl pallet_chain_origin::Trait for Runtime {
+    type Event = Event;
+    type Origin = Origin;
+    type Call = Call;
+    type RootDispatchOrigin = EnsureRelay<Origin>;
+    // Relay-chain calls are dispatched by `InboundQueue`, which weighs every message the same.
+    // `dispatch_as_root` is exempt, so that the relay chain can always upgrade the runtime.
+    type MaxRelayCallWeight = WeightPerInboundMessage;
+}
+
+impl pallet_hrmp_channels::Trait for Runtime {
     type Event = Event;
+    type SelfParaId = ParachainInfo;
+    type UpwardMessage = UpwardMessage;
     type UpwardMessageSender = MessageBroker;
+}
+
+impl parachain_info::Trait for Runtime {
+    type UpdateOrigin = EnsureRootOrRelay;
+    type WeightInfo = weights::parachain_info::WeightInfo;
+}
+
+impl token_dealer::Trait for Runtime {
+    type Event = Event;
+    type UpwardMessageSender = impls::TransferSender;
     type UpwardMessage = token_dealer::upward_messages::RococoUpwardMessage;
-    type XCMPMessageSender = MessageBroker;
+    type XCMPMessageSender = impls::TransferSender;
     type Currency = Balances;
 }
 
@@ -253,9 +689,35 @@ construct_runtime! {
         ParachainUpgrade: cumulus_parachain_upgrade::{Module, Call, Storage, Inherent, Event},
         MessageBroker: cumulus_message_broker::{Module, Call, Inherent, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Module, Storage},
-        ParachainInfo: parachain_info::{Module, Storage, Config},
+        ParachainInfo: parachain_info::{Module, Call, Storage, Config},
         TokenDealer: token_dealer::{Module, Call, Event<T>},
-        Assets: pallet_assets::{Module, Call, Storage, Event<T>},
+        // The calls of `Assets` are dispatched through `AssetCalls`, which reports the balances
+        // they change.
+        Assets: pallet_assets::{Module, Storage, Event<T>},
+        Authorship: pallet_authorship::{Module, Call, Storage},
+        CollatorSelection: pallet_collator_selection::{Module, Call, Storage, Event<T>, Config<T>},
+        Session: pallet_session::{Module, Call, Storage, Event, Config<T>},
+        Aura: pallet_aura::{Module, Config<T>, Inherent(Timestamp)},
+        CollatorRewards: pallet_collator_rewards::{Module, Call, Storage, Event<T>, Config},
+        Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+        TechnicalCommittee: pallet_collective::<Instance2>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+        Scheduler: pallet_scheduler::{Module, Call, Storage, Event<T>},
+        Democracy: pallet_democracy::{Module, Call, Storage, Config, Event<T>},
+        Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
+        AssetTreasury: pallet_asset_treasury::{Module, Call, Storage, Event<T>},
+        AssetTxPayment: pallet_asset_tx_payment::{Module, Call, Storage, Event<T>},
+        AssetIndex: pallet_asset_index::{Module, Storage},
+        AssetCalls: pallet_asset_calls::{Module, Call},
+        AssetMetadata: pallet_asset_metadata::{Module, Call, Storage, Event<T>, Config<T>},
+        AssetRegistry: pallet_asset_registry::{Module, Call, Storage, Event<T>, Config<T>},
+        AssetDealer: pallet_asset_dealer::{Module, Call, Storage, Event<T>, Config<T>},
+        DepositGuard: pallet_deposit_guard::{Module, Call, Storage, Event<T>},
+        ReplayGuard: pallet_replay_guard::{Module, Call, Storage, Event<T>},
+        InboundQueue: pallet_inbound_queue::{Module, Call, Storage, Event, Config},
+        SafeMode: pallet_safe_mode::{Module, Call, Storage, Event<T>},
+        ChainOrigin: pallet_chain_origin::{Module, Call, Event, Origin},
+        RemoteCall: pallet_remote_call::{Module, Call, Event<T>},
+        HrmpChannels: pallet_hrmp_channels::{Module, Call, Storage, Event, Config},
     }
 }
 
@@ -276,7 +738,7 @@ pub type SignedExtra = (
     frame_system::CheckEra<Runtime>,
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
-    pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    pallet_asset_tx_payment::ChargeAssetTxPayment<Runtime>,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -351,6 +813,92 @@ impl_runtime_apis! {
         }
     }
 
+    impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
+        fn slot_duration() -> u64 {
+            Aura::slot_duration()
+        }
+
+        fn authorities() -> Vec<AuraId> {
+            Aura::authorities()
+        }
+    }
+
+    impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
+        fn account_nonce(account: AccountId) -> Index {
+            System::account_nonce(account)
+        }
+    }
+
+    impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance>
+        for Runtime
+    {
+        fn query_info(
+            uxt: <Block as BlockT>::Extrinsic,
+            len: u32,
+        ) -> pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo<Balance> {
+            TransactionPayment::query_info(uxt, len)
+        }
+    }
+
+    impl parachain_primitives::FeeMultiplierApi<Block> for Runtime {
+        fn next_fee_multiplier() -> Multiplier {
+            TransactionPayment::next_fee_multiplier()
+        }
+    }
+
+    impl parachain_primitives::InboundQueueApi<Block> for Runtime {
+        fn inbound_queue_depth() -> u64 {
+            InboundQueue::queue_depth()
+        }
+    }
+
+    impl parachain_primitives::HrmpChannelsApi<Block> for Runtime {
+        fn hrmp_channels() -> Vec<HrmpChannel> {
+            HrmpChannels::channels()
+        }
+    }
+
+    impl parachain_primitives::AssetsApi<Block, AccountId, AssetId, Balance> for Runtime {
+        fn balance(asset_id: AssetId, who: AccountId) -> Balance {
+            AssetsAdapter::balance(asset_id, &who)
+        }
+
+        fn total_supply(asset_id: AssetId) -> Balance {
+            AssetsAdapter::total_supply(asset_id)
+        }
+
+        fn asset_details(asset_id: AssetId) -> Option<AssetDetails<AccountId, Balance>> {
+            let total_supply = AssetsAdapter::total_supply(asset_id);
+
+            // Assets deposited by the token dealer may lie outside the ids issued by the pallet.
+            if asset_id < Assets::next_asset_id() || !total_supply.is_zero() {
+                Some(AssetDetails { total_supply, owner: AssetMetadata::owner(asset_id) })
+            } else {
+                None
+            }
+        }
+
+        fn asset_metadata(asset_id: AssetId) -> Option<parachain_primitives::AssetMetadata> {
+            AssetMetadata::metadata(asset_id)
+        }
+
+        fn account_assets(who: AccountId) -> Vec<(AssetId, Balance)> {
+            AssetIndex::account_assets(&who)
+        }
+
+        fn local_asset_id(para_id: ParaId, remote_id: AssetId) -> Option<AssetId> {
+            AssetRegistry::local_asset_id(para_id, remote_id)
+        }
+
+        fn foreign_asset(asset_id: AssetId) -> Option<(ParaId, AssetId)> {
+            AssetRegistry::foreign_asset(asset_id)
+        }
+
+        fn reconcile_reserves(asset_id: AssetId) -> ReserveReconciliation<Balance> {
+            AssetDealer::reconcile_reserves(asset_id)
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn decode_session_keys(
             encoded: Vec<u8>,
@@ -362,6 +910,43 @@ impl_runtime_apis! {
             SessionKeys::generate(seed)
         }
     }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    impl frame_benchmarking::Benchmark<Block> for Runtime {
+        fn dispatch_benchmark(
+            pallet: Vec<u8>,
+            benchmark: Vec<u8>,
+            lowest_range_values: Vec<u32>,
+            highest_range_values: Vec<u32>,
+            steps: Vec<u32>,
+            repeat: u32,
+        ) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+            use frame_benchmarking::{add_benchmark, BenchmarkBatch, Benchmarking};
+
+            use benchmarking::Module as OverheadBench;
+            impl benchmarking::Trait for Runtime {}
+
+            let mut batches = Vec::<BenchmarkBatch>::new();
+            let params = (
+                &pallet,
+                &benchmark,
+                &lowest_range_values,
+                &highest_range_values,
+                &steps,
+                repeat,
+            );
+
+            add_benchmark!(params, batches, b"balances", Balances);
+            add_benchmark!(params, batches, b"timestamp", Timestamp);
+            add_benchmark!(params, batches, b"parachain-info", ParachainInfo);
+            add_benchmark!(params, batches, b"overhead", OverheadBench::<Runtime>);
+
+            if batches.is_empty() {
+                return Err("Benchmark not found for this pallet.".into());
+            }
+            Ok(batches)
+        }
+    }
 }
 
 cumulus_runtime::register_validate_block!(Block, Executive);
@@ -0,0 +1,10 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Weights of the dispatchables of the pallets in the runtime.
+//!
+//! None of them is measured on collator hardware yet: they are placeholders to regenerate by
+//! benchmarking a runtime built with the `runtime-benchmarks` feature.
+
+pub mod pallet_balances;
+pub mod pallet_timestamp;
+pub mod parachain_info;
@@ -0,0 +1,38 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Weights for `pallet_balances`.
+//!
+//! Placeholder: these are the Substrate reference machine results, not measured on collator
+//! hardware. Replace with the output of `benchmark --pallet balances`.
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+pub struct WeightInfo;
+
+impl pallet_balances::WeightInfo for WeightInfo {
+    fn transfer(_u: u32, _e: u32) -> Weight {
+        (94_838_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn transfer_best_case(_u: u32, _e: u32) -> Weight {
+        (63_201_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn transfer_keep_alive(_u: u32, _e: u32) -> Weight {
+        (64_556_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn set_balance(_u: u32, _e: u32) -> Weight {
+        (36_204_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn set_balance_killing(_u: u32, _e: u32) -> Weight {
+        (45_620_000 as Weight)
+            .saturating_add(DbWeight::get().reads(1 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+}
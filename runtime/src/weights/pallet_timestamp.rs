@@ -0,0 +1,21 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+
+//! Weights for `pallet_timestamp`.
+//!
+//! Placeholder: these are the Substrate reference machine results, not measured on collator
+//! hardware. Replace with the output of `benchmark --pallet timestamp`.
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+pub struct WeightInfo;
+
+impl pallet_timestamp::WeightInfo for WeightInfo {
+    fn set(_t: u32) -> Weight {
+        (11_650_000 as Weight)
+            .saturating_add(DbWeight::get().reads(2 as Weight))
+            .saturating_add(DbWeight::get().writes(1 as Weight))
+    }
+    fn on_finalize(_t: u32) -> Weight {
+        (6_681_000 as Weight)
+    }
+}